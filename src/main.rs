@@ -8,14 +8,104 @@ extern crate union_find;
 extern crate fnv;
 
 mod player;
+mod tournament;
 
+use player::Player;
 use player::htp::HTP;
-use player::mctsplayer::MCTSPlayer;
-use std::io;
+use player::mctsplayer::{MCTSPlayer, SearchConfig};
+use player::bookbuilder::{self, BuildConfig};
+use tournament::TournamentConfig;
+use std::io::{self, Write, BufReader};
+use std::env;
+use std::process;
+
+/// Parse `--name value` pairs from the command line into a search config, a book build config
+/// and an optional random seed. Dashes in names are accepted in place of underscores, so
+/// `--num-threads 2` and `--num_threads 2` are equivalent.
+fn parse_args<I>(mut args: I) -> Result<(SearchConfig, BuildConfig, Option<u64>), String>
+    where I: Iterator<Item = String>
+{
+    let mut config = SearchConfig::new();
+    let mut build = BuildConfig::new();
+    let mut seed = None;
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("unexpected argument: {}", arg));
+        }
+        let name = arg[2..].replace("-", "_");
+        let value = try!(args.next().ok_or(format!("missing value for {}", arg)));
+        if name == "seed" {
+            seed = Some(try!(value.parse().map_err(|_| format!("invalid seed: {}", value))));
+        } else if build.params().iter().any(|&(n, _)| n == name) {
+            try!(build.set(&name, &value));
+        } else {
+            try!(config.set(&name, &value));
+        }
+    }
+    Ok((config, build, seed))
+}
 
 fn main() {
-    let player = MCTSPlayer::new();
-    let (stdin, stdout) = (io::stdin(), io::stdout());
-    let mut htp = HTP::new(stdin.lock(), stdout.lock());
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // `tournament` plays two HTP programs against each other
+    if args.first().map_or(false, |arg| arg == "tournament") {
+        let result = TournamentConfig::parse(args.into_iter().skip(1))
+                         .map_err(|e| format!("{}\n{}", e, tournament::USAGE))
+                         .and_then(|config| tournament::run(&config));
+        if let Err(e) = result {
+            writeln!(io::stderr(), "{}", e).unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+    // `summary` analyzes the results of a tournament
+    if args.first().map_or(false, |arg| arg == "summary") {
+        if let Err(e) = tournament::analysis::run(args.into_iter().skip(1)) {
+            writeln!(io::stderr(), "{}\n{}", e, tournament::analysis::USAGE).unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+    // `book-build <file>` grows an opening book instead of playing over HTP
+    let book_path = if args.len() >= 2 && args[0] == "book-build" {
+        let path = args[1].clone();
+        args.drain(..2);
+        Some(path)
+    } else {
+        None
+    };
+    let (config, build, seed) = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(e) => {
+            let mut usage = String::from("usage: coronene [--seed <seed>] [--<param> <value>]...\n\
+                                          \x20      coronene book-build <file> \
+                                          [--<param> <value>]...\n\
+                                          \x20      coronene tournament [options]...\n\
+                                          \x20      coronene summary <results> [options]...\n\
+                                          params:");
+            for (name, value) in SearchConfig::new().params() {
+                usage.push_str(&format!("\n    --{} (default {})", name, value));
+            }
+            usage.push_str("\nbook-build params:");
+            for (name, value) in BuildConfig::new().params() {
+                usage.push_str(&format!("\n    --{} (default {})", name, value));
+            }
+            writeln!(io::stderr(), "{}\n{}", e, usage).unwrap();
+            process::exit(1);
+        }
+    };
+    let mut player = MCTSPlayer::new(config);
+    if let Some(seed) = seed {
+        player.set_seed(seed);
+    }
+    if let Some(path) = book_path {
+        if let Err(e) = bookbuilder::build(&mut player, &build, &path) {
+            writeln!(io::stderr(), "{}", e).unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+    let stdout = io::stdout();
+    let mut htp = HTP::new(BufReader::new(io::stdin()), stdout.lock());
     htp.run(player);
 }
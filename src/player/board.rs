@@ -55,7 +55,39 @@ impl From<Color> for bool {
     }
 }
 
-pub type Coord = i8;
+pub type Coord = i16;
+
+/// Name a column following the HexGui convention: `a` to `z`, then `aa`, `ab`, ..., `az`, `ba`
+/// and so on, like spreadsheet columns.
+pub fn column_name(x: Coord) -> String {
+    let mut name = Vec::new();
+    let mut x = x as i32 + 1;
+    while x > 0 {
+        x -= 1;
+        name.push(b'a' + (x % 26) as u8);
+        x /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// Parse a column name produced by `column_name`.
+pub fn parse_column(name: &str) -> Option<Coord> {
+    if name.is_empty() {
+        return None;
+    }
+    let mut x: i32 = 0;
+    for c in name.bytes() {
+        if c < b'a' || c > b'z' {
+            return None;
+        }
+        x = x * 26 + (c - b'a') as i32 + 1;
+        if x > Coord::max_value() as i32 {
+            return None;
+        }
+    }
+    Some((x - 1) as Coord)
+}
 
 #[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
 pub struct Pos {
@@ -77,17 +109,16 @@ impl FromStr for Pos {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s = s.to_lowercase();
-        let b = s.as_bytes();
-        let x = if b[0] >= b'a' && b[0] <= b'z' {
-            b[0] - b'a'
-        } else {
-            return Err(());
+        let split = s.find(|c: char| !c.is_ascii_lowercase()).unwrap_or(s.len());
+        let x = match parse_column(&s[..split]) {
+            Some(x) => x,
+            None => return Err(()),
         };
-        let y = match s[1..].parse::<Coord>() {
-            Ok(y) => y - 1,
-            Err(_) => return Err(()),
+        let y = match s[split..].parse::<Coord>() {
+            Ok(y) if y > 0 => y - 1,
+            _ => return Err(()),
         };
-        Ok(Pos::new(x as Coord, y as Coord))
+        Ok(Pos::new(x, y))
     }
 }
 
@@ -105,7 +136,7 @@ impl<'a> From<&'a str> for Pos {
 
 impl fmt::Display for Pos {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}", (self.x + 'A' as Coord) as u8 as char, self.y + 1)
+        write!(f, "{}{}", column_name(self.x).to_uppercase(), self.y + 1)
     }
 }
 
@@ -120,6 +151,16 @@ impl Add for Pos {
     }
 }
 
+/// The two ways of using the swap rule, as in HexGui.
+#[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
+pub enum SwapKind {
+    /// The first stone is replaced by one of the swapping player's color, reflected in the long
+    /// diagonal. The players keep their colors.
+    Pieces,
+    /// The players exchange colors and the board is left as it is.
+    Sides,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
 pub enum Move {
     Resign,
@@ -128,6 +169,12 @@ pub enum Move {
         color: Color,
         pos: Pos,
     },
+    /// Swap after the first move. Only the second player may do this, and only as their first
+    /// move.
+    Swap {
+        color: Color,
+        kind: SwapKind,
+    },
 }
 
 impl Move {
@@ -149,10 +196,31 @@ impl Move {
     }
 
     pub fn color(&self) -> Option<Color> {
-        if let &Move::Play { color, pos: _ } = self {
-            Some(color)
-        } else {
-            None
+        match self {
+            &Move::Play { color, pos: _ } => Some(color),
+            &Move::Swap { color, kind: _ } => Some(color),
+            _ => None,
+        }
+    }
+
+    /// Parse a move made by `color`, in the format used by HTP.
+    pub fn parse(color: Color, s: &str) -> Result<Move, ()> {
+        match &s.to_lowercase()[..] {
+            "resign" => Ok(Move::Resign),
+            "pass" => Ok(Move::None),
+            "swap-pieces" => {
+                Ok(Move::Swap {
+                    color: color,
+                    kind: SwapKind::Pieces,
+                })
+            }
+            "swap-sides" => {
+                Ok(Move::Swap {
+                    color: color,
+                    kind: SwapKind::Sides,
+                })
+            }
+            s => s.parse::<Pos>().map(|pos| Move::new(color, pos)),
         }
     }
 }
@@ -163,10 +231,23 @@ impl fmt::Display for Move {
             &Move::Resign => write!(f, "resign"),
             &Move::None => write!(f, "pass"),
             &Move::Play { color: _, pos } => write!(f, "{}", pos),
+            &Move::Swap { color: _, kind: SwapKind::Pieces } => write!(f, "swap-pieces"),
+            &Move::Swap { color: _, kind: SwapKind::Sides } => write!(f, "swap-sides"),
         }
     }
 }
 
+/// What is needed to take back a move made with `Board::make_move`.
+#[derive(Clone, Debug)]
+struct Undo {
+    m: Move,
+    /// The union-find structures changed by the move, indexed by color, as they were before it.
+    groups: Vec<(usize, QuickUnionUf<UnionBySize>)>,
+    to_play: Color,
+    last_move: Move,
+    winner: Option<Color>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Board {
     dims: Pos,
@@ -176,6 +257,10 @@ pub struct Board {
     groups: Vec<QuickUnionUf<UnionBySize>>,
     last_move: Move,
     winner: Option<Color>,
+    /// Number of stones played and swaps made so far.
+    move_count: usize,
+    /// Moves made with `make_move`, which can be taken back with `undo`.
+    history: Vec<Undo>,
 }
 
 impl Board {
@@ -189,6 +274,23 @@ impl Board {
             groups: vec![QuickUnionUf::new(dims.area() + 2); 2],
             last_move: Move::None,
             winner: None,
+            move_count: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Copy the position without its undo history, which makes the copy much cheaper.
+    pub fn snapshot(&self) -> Board {
+        Board {
+            dims: self.dims,
+            colors: self.colors.clone(),
+            empty_cells: self.empty_cells.clone(),
+            to_play: self.to_play,
+            groups: self.groups.clone(),
+            last_move: self.last_move,
+            winner: self.winner,
+            move_count: self.move_count,
+            history: Vec::new(),
         }
     }
 
@@ -212,15 +314,130 @@ impl Board {
         match m {
             Move::Resign | Move::None => true,
             Move::Play { color, pos } => {
-                if !self.is_empty(pos) {
+                if !self.on_board(pos) || !self.is_empty(pos) {
                     false
                 } else {
                     self.to_play = color.invert();
                     self.last_move = m;
+                    self.move_count += 1;
                     self.set(pos, Some(color))
                 }
             }
+            Move::Swap { color, kind } => {
+                if !self.can_swap(color) {
+                    return false;
+                }
+                match kind {
+                    SwapKind::Pieces => {
+                        let pos = self.last_move.pos().unwrap();
+                        if !self.on_board((pos.y, pos.x)) {
+                            return false;
+                        }
+                        self.clear_cell(pos);
+                        self.set((pos.y, pos.x), Some(color));
+                        self.to_play = color.invert();
+                    }
+                    SwapKind::Sides => {
+                        // the swapping player takes over the first stone, so the other player,
+                        // who now has our color, moves next
+                        self.to_play = color;
+                    }
+                }
+                self.last_move = m;
+                self.move_count += 1;
+                true
+            }
+        }
+    }
+
+    /// A value identifying the position: the stones on the board and the player to move.
+    pub fn position_key(&self) -> (Vec<Move>, Color) {
+        (self.iter_filled().collect(), self.to_play)
+    }
+
+    /// Play a move so that it can be taken back later with `undo`. Unlike `play`, this also records
+    /// moves which don't change the board, such as resignations, so that the history matches the
+    /// game record.
+    pub fn make_move(&mut self, m: Move) -> bool {
+        let groups = match m {
+            Move::Play { color, pos: _ } => {
+                let i = color as usize;
+                vec![(i, self.groups[i].clone())]
+            }
+            Move::Swap { color: _, kind: SwapKind::Pieces } => {
+                self.groups.iter().cloned().enumerate().collect()
+            }
+            _ => Vec::new(),
+        };
+        let undo = Undo {
+            m: m,
+            groups: groups,
+            to_play: self.to_play,
+            last_move: self.last_move,
+            winner: self.winner,
+        };
+        if self.play(m) {
+            self.history.push(undo);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take back the last move made with `make_move`, returning it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let undo = match self.history.pop() {
+            Some(undo) => undo,
+            None => return None,
+        };
+        match undo.m {
+            Move::Play { color: _, pos } => {
+                let idx = self.idx_of(pos).unwrap();
+                self.empty_cells.set(idx, true);
+                self.move_count -= 1;
+            }
+            Move::Swap { color: _, kind } => {
+                if kind == SwapKind::Pieces {
+                    let pos = undo.last_move.pos().unwrap();
+                    let swapped = self.idx_of((pos.y, pos.x)).unwrap();
+                    self.empty_cells.set(swapped, true);
+                    let idx = self.idx_of(pos).unwrap();
+                    self.empty_cells.set(idx, false);
+                    self.colors.set(idx, undo.last_move.color().unwrap().into());
+                }
+                self.move_count -= 1;
+            }
+            _ => (),
         }
+        for (i, groups) in undo.groups {
+            self.groups[i] = groups;
+        }
+        self.to_play = undo.to_play;
+        self.last_move = undo.last_move;
+        self.winner = undo.winner;
+        Some(undo.m)
+    }
+
+    /// Place setup stones, as given by a game record. Unlike moves, they are not part of the
+    /// history, so they can't be taken back, and they don't change whose turn it is. They do count
+    /// as stones played, so there is no swapping after them. Returns false if one of them is not a
+    /// stone on an empty cell.
+    pub fn set_up(&mut self, stones: &[Move]) -> bool {
+        let to_play = self.to_play;
+        for &m in stones {
+            match m {
+                Move::Play { .. } if self.play(m) => (),
+                _ => return false,
+            }
+        }
+        self.to_play = to_play;
+        self.last_move = Move::None;
+        true
+    }
+
+    /// Check whether `color` may swap: exactly one stone has been played, by the other player.
+    pub fn can_swap(&self, color: Color) -> bool {
+        self.move_count == 1 && self.last_move.color() == Some(color.invert())
     }
 
     pub fn iter_empty<'a>(&'a self) -> impl Iterator<Item = Pos> + 'a {
@@ -353,30 +570,31 @@ impl Board {
     fn idx_of<P: Into<Pos>>(&self, pos: P) -> Option<usize> {
         let pos = pos.into();
         if self.on_board(pos) {
-            Some(pos.y as usize * self.dims.y as usize + pos.x as usize)
+            Some(pos.y as usize * self.dims.x as usize + pos.x as usize)
         } else {
             None
         }
     }
 
+    /// The union-find index of a cell, or of the edge adjacent to an off-board position.
     fn group_idx<P: Into<Pos>>(&self, pos: P) -> Option<usize> {
         let pos = pos.into();
         if let Some(idx) = self.idx_of(pos) {
             Some(idx)
         } else {
-            if pos.x == -1 || pos.y == -1 {
-                Some(self.edge_idx(0))
-            } else if pos.x == self.dims.x || pos.y == self.dims.y {
-                Some(self.edge_idx(1))
-            } else {
-                None
+            match self.get(pos) {
+                Some(Color::White) if pos.x < 0 => Some(self.edge_idx(0)),
+                Some(Color::White) => Some(self.edge_idx(1)),
+                Some(Color::Black) if pos.y < 0 => Some(self.edge_idx(0)),
+                Some(Color::Black) => Some(self.edge_idx(1)),
+                None => None,
             }
         }
     }
 
     fn pos_of(&self, idx: usize) -> Pos {
         ((idx % self.dims.x as usize) as Coord,
-        (idx / self.dims.y as usize) as Coord)
+        (idx / self.dims.x as usize) as Coord)
             .into()
     }
 
@@ -385,18 +603,64 @@ impl Board {
     }
 }
 
+/// Read access to the cells of a position, so that the rollout patterns can look at either a
+/// `Board` or a `PlayoutBoard`.
+pub trait BoardView {
+    /// The color of a cell, or of the edge for positions just off the board.
+    fn get(&self, pos: Pos) -> Option<Color>;
+    fn on_board(&self, pos: Pos) -> bool;
+    fn to_play(&self) -> Color;
+    fn last_move(&self) -> Move;
+}
+
+impl BoardView for Board {
+    fn get(&self, pos: Pos) -> Option<Color> {
+        Board::get(self, pos)
+    }
+
+    fn on_board(&self, pos: Pos) -> bool {
+        Board::on_board(self, pos)
+    }
+
+    fn to_play(&self) -> Color {
+        Board::to_play(self)
+    }
+
+    fn last_move(&self) -> Move {
+        Board::last_move(self)
+    }
+}
+
+impl Board {
+    /// Write the column names, one character per line, right aligned so that multi-letter names
+    /// line up with their column.
+    fn fmt_columns(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+        let names: Vec<String> = (0..self.dims.x).map(column_name).collect();
+        let height = names.iter().map(|name| name.len()).max().unwrap_or(0);
+        for line in 0..height {
+            if line > 0 {
+                try!(write!(f, "\n"));
+            }
+            try!(write!(f, "{:1$}", "", indent));
+            for name in &names {
+                let c = (line + name.len()).checked_sub(height).and_then(|i| name.chars().nth(i));
+                try!(write!(f, "{} ", c.unwrap_or(' ')));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        try!(write!(f, "  "));
-        for x in 0..self.dims.x {
-            try!(write!(f, "{} ", (x + 'a' as Coord) as u8 as char));
-        }
+        let width = self.dims.y.to_string().len().max(2);
+        try!(self.fmt_columns(f, width));
         for y in 0..self.dims.y {
             try!(write!(f, "\n"));
             for _ in 0..y {
                 try!(write!(f, " "));
             }
-            try!(write!(f, "{:2}\\", y + 1));
+            try!(write!(f, "{:1$}\\", y + 1, width));
             for x in 0..self.dims.x {
                 match self.get((x, y)) {
                     Some(c) => try!(write!(f, "{}", c)),
@@ -408,13 +672,188 @@ impl fmt::Display for Board {
             }
             try!(write!(f, "\\{}", y + 1));
         }
-        try!(write!(f, "\n   "));
-        for _ in 0..self.dims.y {
-            try!(write!(f, " "));
+        try!(write!(f, "\n"));
+        self.fmt_columns(f, self.dims.y as usize + width + 1)
+    }
+}
+
+/// Find the winner by flood filling from one edge of each color, without union-find.
+#[cfg(test)]
+fn naive_winner(board: &Board) -> Option<Color> {
+    let dims = board.dimensions();
+    for &color in &[Color::Black, Color::White] {
+        let mut stack: Vec<Pos> = Vec::new();
+        let mut seen = vec![false; dims.area()];
+        let idx = |pos: Pos| pos.y as usize * dims.x as usize + pos.x as usize;
+        let (len, across) = match color {
+            Color::Black => (dims.x, dims.y),
+            Color::White => (dims.y, dims.x),
+        };
+        for i in 0..len {
+            let pos = match color {
+                Color::Black => Pos::new(i, 0),
+                Color::White => Pos::new(0, i),
+            };
+            if board.get(pos) == Some(color) {
+                seen[idx(pos)] = true;
+                stack.push(pos);
+            }
         }
-        for x in 0..self.dims.x {
-            try!(write!(f, "{} ", (x + 'a' as Coord) as u8 as char));
+        while let Some(pos) = stack.pop() {
+            let reached = match color {
+                Color::Black => pos.y,
+                Color::White => pos.x,
+            };
+            if reached == across - 1 {
+                return Some(color);
+            }
+            for &d in &[(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)] {
+                let n = pos + d.into();
+                if board.on_board(n) && board.get(n) == Some(color) &&
+                   !seen[idx(n)] {
+                    seen[idx(n)] = true;
+                    stack.push(n);
+                }
+            }
         }
-        Ok(())
+    }
+    None
+}
+
+/// Find the winner with perfect play by searching every line, which is only feasible with a few
+/// empty cells.
+#[cfg(test)]
+pub fn solve(board: &Board) -> Color {
+    let to_play = board.to_play();
+    for pos in board.iter_empty() {
+        let mut next = board.snapshot();
+        next.play(Move::new(to_play, pos));
+        if next.winner() == Some(to_play) || solve(&next) == to_play {
+            return to_play;
+        }
+    }
+    to_play.invert()
+}
+
+#[test]
+fn test_pos_names() {
+    for &(name, x) in &[("a", 0), ("z", 25), ("aa", 26), ("az", 51), ("ba", 52), ("zz", 701),
+                        ("aaa", 702)] {
+        assert_eq!(column_name(x), name);
+        assert_eq!(parse_column(name), Some(x));
+    }
+    for x in 0..1000 {
+        let pos = Pos::new(x, x / 3);
+        assert_eq!(pos.to_string().parse(), Ok(pos));
+    }
+    assert_eq!("AF32".parse(), Ok(Pos::new(31, 31)));
+    for bad in &["", "a", "1", "a0", "a-1", "1a", "a1b", "swap-pieces"] {
+        assert_eq!(bad.parse::<Pos>(), Err(()));
+    }
+}
+
+#[test]
+fn test_winner_all_shapes() {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    for cols in 1..20 {
+        for rows in 1..20 {
+            for _ in 0..2 {
+                let mut board = Board::new((cols, rows));
+                let mut cells: Vec<Pos> = board.iter_empty().collect();
+                assert_eq!(cells.len(), board.dimensions().area());
+                rng.shuffle(&mut cells);
+                for (i, &pos) in cells.iter().enumerate() {
+                    assert!(board.on_board(pos));
+                    assert!(board.play(Move::new(Color::from(i % 2 == 1), pos)));
+                    assert_eq!(board.winner(), naive_winner(&board));
+                }
+                // a full board always has a winner
+                assert!(board.winner().is_some());
+                assert_eq!(board.iter_filled().count(), cells.len());
+                for m in board.iter_filled() {
+                    assert_eq!(board.get(m.pos().unwrap()), m.color());
+                }
+
+                // removing stones must give the same result as never having played them
+                for &pos in cells.iter().rev().take(cells.len() / 2) {
+                    board.clear_cell(pos);
+                    assert_eq!(board.winner(), naive_winner(&board));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_swap() {
+    let mut board = Board::new((5, 5));
+    // a rejected move must not count towards the opening
+    assert!(!board.play(Move::new(Color::Black, "z9")));
+    assert!(!board.play(Move::new(Color::Black, "f1")));
+    assert_eq!(board.to_play(), Color::Black);
+    assert_eq!(board.last_move(), Move::None);
+    assert!(board.play(Move::new(Color::Black, "a1")));
+    assert!(board.play(Move::Swap {
+        color: Color::White,
+        kind: SwapKind::Pieces,
+    }));
+    assert_eq!(board.get("a1"), Some(Color::White));
+    assert_eq!(board.to_play(), Color::Black);
+}
+
+#[test]
+fn test_undo() {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    let mut rng = XorShiftRng::from_seed([4, 3, 2, 1]);
+    for size in 1..12 {
+        let mut board = Board::new((size, size));
+        let mut cells: Vec<Pos> = board.iter_empty().collect();
+        rng.shuffle(&mut cells);
+        let mut positions = Vec::new();
+        for (i, &pos) in cells.iter().enumerate() {
+            if !board.is_empty(pos) {
+                continue; // taken by the swapped stone
+            }
+            positions.push(board.snapshot());
+            assert!(board.make_move(Move::new(Color::from(i % 2 == 1), pos)));
+            if i == 0 {
+                // rejected moves must leave the board and its history as they were
+                for &m in &[Move::new(Color::White, pos), Move::new(Color::White, (size, 0))] {
+                    assert!(!board.make_move(m));
+                    assert_eq!(board.to_play(), Color::White);
+                    assert_eq!(board.last_move(), Move::new(Color::Black, pos));
+                    assert!(board.can_swap(Color::White));
+                }
+                positions.push(board.snapshot());
+                let swap = Move::Swap {
+                    color: Color::White,
+                    kind: SwapKind::Pieces,
+                };
+                assert!(board.make_move(swap));
+                assert!(!board.make_move(swap));
+            }
+            if i % 5 == 0 {
+                positions.push(board.snapshot());
+                assert!(board.make_move(Move::Resign));
+            }
+        }
+        while let Some(expected) = positions.pop() {
+            assert!(board.undo().is_some());
+            assert_eq!(board.winner(), expected.winner());
+            assert_eq!(board.to_play(), expected.to_play());
+            assert_eq!(board.last_move(), expected.last_move());
+            assert_eq!(board.iter_filled().collect::<Vec<_>>(),
+                       expected.iter_filled().collect::<Vec<_>>());
+            // the restored union-find must keep working as stones are added again
+            let mut a = board.snapshot();
+            let mut b = expected.snapshot();
+            for (i, pos) in expected.iter_empty().enumerate() {
+                let m = Move::new(Color::from(i % 3 == 0), pos);
+                assert!(a.play(m) && b.play(m));
+                assert_eq!(a.winner(), b.winner());
+            }
+        }
+        assert_eq!(board.undo(), None);
     }
 }
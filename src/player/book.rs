@@ -0,0 +1,326 @@
+//! Opening books: moves known to be good in particular positions, with statistics on how often
+//! they win.
+//!
+//! A book is a text file with one move on each line:
+//!
+//! ```text
+//! # size  to-play  black   white  move  wins  visits
+//! 9x9     B        -       -      E5    620   1200
+//! 9x9     W        E5      -      D6    290   580
+//! ```
+//!
+//! The stones of each color are listed separated by commas, or `-` if there are none. Wins are
+//! counted for the player making the move, and may be fractional. Turning the board by 180° gives
+//! an equivalent position, so each entry also covers the position turned around.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use super::board::{Board, Color, Coord, Move, Pos};
+
+/// A move of a book position and its statistics.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BookMove {
+    pub m: Move,
+    pub wins: f32,
+    pub visits: usize,
+}
+
+impl BookMove {
+    pub fn win_rate(&self) -> f32 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.wins / self.visits as f32
+        }
+    }
+}
+
+/// A position, turned whichever way makes its stones sort first.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    dims: Pos,
+    to_play: Color,
+    /// The stones of each color as (row, column), in order.
+    black: Vec<(Coord, Coord)>,
+    white: Vec<(Coord, Coord)>,
+}
+
+impl Key {
+    /// The key of a position, and whether the position had to be turned around to get it.
+    fn new(board: &Board) -> (Key, bool) {
+        let dims = board.dimensions();
+        let stones = |rotated: bool, color: Color| {
+            let mut stones: Vec<(Coord, Coord)> =
+                board.iter_filled()
+                     .filter(|m| m.color() == Some(color))
+                     .filter_map(|m| m.pos())
+                     .map(|pos| if rotated { rotate(dims, pos) } else { pos })
+                     .map(|pos| (pos.y, pos.x))
+                     .collect();
+            stones.sort();
+            stones
+        };
+        let key = |rotated: bool| {
+            Key {
+                dims: dims,
+                to_play: board.to_play(),
+                black: stones(rotated, Color::Black),
+                white: stones(rotated, Color::White),
+            }
+        };
+        let (key, turned) = (key(false), key(true));
+        if (&turned.black, &turned.white) < (&key.black, &key.white) {
+            (turned, true)
+        } else {
+            (key, false)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Book {
+    positions: HashMap<Key, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn new() -> Book {
+        Book { positions: HashMap::new() }
+    }
+
+    pub fn load(path: &str) -> Result<Book, String> {
+        let mut text = String::new();
+        try!(File::open(path)
+                 .and_then(|mut f| f.read_to_string(&mut text))
+                 .map_err(|e| format!("cannot read {}: {}", path, e)));
+        Book::parse(&text)
+    }
+
+    /// Write the book to a file. The old file is only replaced once the new one is complete, so
+    /// it survives being interrupted.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let tmp = format!("{}.tmp", path);
+        File::create(&tmp)
+            .and_then(|mut f| write!(f, "{}", self))
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Book, String> {
+        let mut book = Book::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {}", i + 1, msg);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() != 7 {
+                return Err(err("expected size, player, stones, move, wins and visits"));
+            }
+            let dims = try!(parse_size(words[0]).ok_or(err("invalid size")));
+            let to_play: Color = try!(words[1].parse().map_err(|_| err("invalid player")));
+            let mut board = Board::new(dims);
+            for &(color, stones) in &[(Color::Black, words[2]), (Color::White, words[3])] {
+                for stone in stones.split(',').filter(|&s| s != "-") {
+                    let pos = try!(stone.parse::<Pos>().map_err(|_| err("invalid stone")));
+                    if !board.play(Move::new(color, pos)) {
+                        return Err(err("invalid stone"));
+                    }
+                }
+            }
+            board.set_to_play(to_play);
+            let m = try!(Move::parse(to_play, words[4]).map_err(|_| err("invalid move")));
+            let wins = try!(words[5].parse::<f32>().map_err(|_| err("invalid wins")));
+            let visits = try!(words[6].parse().map_err(|_| err("invalid visits")));
+            book.set(&board,
+                     BookMove {
+                         m: m,
+                         wins: wins,
+                         visits: visits,
+                     });
+        }
+        Ok(book)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// The moves known for a position.
+    pub fn moves(&self, board: &Board) -> Vec<BookMove> {
+        let (key, turned) = Key::new(board);
+        self.positions.get(&key).map_or(Vec::new(), |moves| {
+            moves.iter().map(|&entry| turn(board.dimensions(), entry, turned)).collect()
+        })
+    }
+
+    /// The move with the best win rate among those played at least `min_visits` times.
+    pub fn best_move(&self, board: &Board, min_visits: usize) -> Option<Move> {
+        self.moves(board)
+            .into_iter()
+            .filter(|entry| entry.visits >= min_visits.max(1))
+            .fold(None, |best: Option<BookMove>, entry| {
+                match best {
+                    Some(best) if best.win_rate() >= entry.win_rate() => Some(best),
+                    _ => Some(entry),
+                }
+            })
+            .map(|entry| entry.m)
+    }
+
+    /// Add a move to a position, or replace its statistics if it is already there.
+    pub fn set(&mut self, board: &Board, entry: BookMove) {
+        let (key, turned) = Key::new(board);
+        let entry = turn(board.dimensions(), entry, turned);
+        let moves = self.positions.entry(key).or_insert_with(Vec::new);
+        match moves.iter().position(|e| e.m == entry.m) {
+            Some(i) => moves[i] = entry,
+            None => moves.push(entry),
+        }
+    }
+
+    /// Remove a move from a position. Returns false if it wasn't in the book.
+    pub fn remove(&mut self, board: &Board, m: Move) -> bool {
+        let (key, turned) = Key::new(board);
+        let m = turn(board.dimensions(),
+                     BookMove {
+                         m: m,
+                         wins: 0.0,
+                         visits: 0,
+                     },
+                     turned)
+                    .m;
+        let removed = match self.positions.get_mut(&key) {
+            Some(moves) => {
+                let len = moves.len();
+                moves.retain(|e| e.m != m);
+                moves.len() < len
+            }
+            None => false,
+        };
+        if self.positions.get(&key).map_or(false, |moves| moves.is_empty()) {
+            self.positions.remove(&key);
+        }
+        removed
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+    }
+}
+
+impl fmt::Display for Book {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stones = |stones: &[(Coord, Coord)]| {
+            if stones.is_empty() {
+                "-".to_string()
+            } else {
+                let names: Vec<String> = stones.iter()
+                                               .map(|&(y, x)| Pos::new(x, y).to_string())
+                                               .collect();
+                names.join(",")
+            }
+        };
+        let mut lines = Vec::new();
+        for (key, moves) in &self.positions {
+            for entry in moves {
+                lines.push(format!("{}x{} {} {} {} {} {} {}",
+                                   key.dims.x,
+                                   key.dims.y,
+                                   key.to_play,
+                                   stones(&key.black),
+                                   stones(&key.white),
+                                   entry.m,
+                                   format_wins(entry.wins),
+                                   entry.visits));
+            }
+        }
+        // the table has no order of its own, so sort to make files easy to compare
+        lines.sort();
+        try!(writeln!(f, "# size to-play black white move wins visits"));
+        for line in lines {
+            try!(writeln!(f, "{}", line));
+        }
+        Ok(())
+    }
+}
+
+/// Format a number of wins with at most three decimals, which is plenty for a win rate and hides
+/// rounding errors.
+fn format_wins(wins: f32) -> String {
+    let wins = format!("{:.3}", wins);
+    wins.trim_end_matches('0').trim_end_matches('.').into()
+}
+
+/// Parse a board size such as `9x9`.
+fn parse_size(size: &str) -> Option<Pos> {
+    let mut parts = size.split('x').map(|n| n.parse::<Coord>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) if x > 0 && y > 0 => Some(Pos::new(x, y)),
+        _ => None,
+    }
+}
+
+/// Turn a cell by 180°.
+fn rotate(dims: Pos, pos: Pos) -> Pos {
+    Pos::new(dims.x - 1 - pos.x, dims.y - 1 - pos.y)
+}
+
+/// Turn a book move by 180° if `turned` is set.
+fn turn(dims: Pos, entry: BookMove, turned: bool) -> BookMove {
+    match entry.m {
+        Move::Play { color, pos } if turned => {
+            BookMove {
+                m: Move::new(color, rotate(dims, pos)),
+                ..entry
+            }
+        }
+        _ => entry,
+    }
+}
+
+#[test]
+fn test_book() {
+    let mut book = Book::parse("# a comment\n\
+                                5x5 B - - C3 6 10\n\
+                                5x5 W A1 - D4 1 2   # the other corner is the same\n\
+                                5x5 W A1 - B4 3 4")
+                       .unwrap();
+    assert_eq!(book.len(), 2);
+
+    // the book answers in both orientations
+    let mut board = Board::new((5, 5));
+    assert_eq!(book.best_move(&board, 1), Some(Move::new(Color::Black, (2, 2))));
+    board.play(Move::new(Color::Black, (0, 0)));
+    assert_eq!(book.best_move(&board, 1), Some(Move::new(Color::White, (1, 3))));
+    assert_eq!(book.best_move(&board, 3), Some(Move::new(Color::White, (1, 3))));
+    assert_eq!(book.best_move(&board, 5), None);
+    let mut turned = Board::new((5, 5));
+    turned.play(Move::new(Color::Black, (4, 4)));
+    assert_eq!(book.best_move(&turned, 1), Some(Move::new(Color::White, (3, 1))));
+
+    // editing from the turned position changes the same entry
+    book.set(&turned,
+             BookMove {
+                 m: Move::new(Color::White, (3, 1)),
+                 wins: 0.0,
+                 visits: 4,
+             });
+    assert_eq!(book.best_move(&board, 1), Some(Move::new(Color::White, (3, 3))));
+    assert!(book.remove(&turned, Move::new(Color::White, (1, 1))));
+    assert!(!book.remove(&turned, Move::new(Color::White, (1, 1))));
+    assert_eq!(book.moves(&board).len(), 1);
+
+    assert_eq!(Book::parse(&book.to_string()), Ok(book));
+    for bad in &["5x5 B - - C3 6", "5x5 X - - C3 6 10", "5x5 B F9 - C3 6 10", "5 B - - C3 6 10",
+                 "5x5 B - - C3 x 10"] {
+        assert!(Book::parse(bad).is_err());
+    }
+}
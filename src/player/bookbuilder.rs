@@ -0,0 +1,250 @@
+//! Growing an opening book from searches, best-first.
+//!
+//! Each expansion walks down the book from the empty board, following the moves which look most
+//! promising, until it reaches a position which isn't in the book yet. That position is searched
+//! with `MCTSPlayer`, its most visited moves are added to the book with their statistics, and the
+//! new values are backed up the path by minimax: a move is worth as much as the best reply is not.
+//!
+//! The book is saved after every expansion, so a build can be interrupted at any time and resumed
+//! by running it again on the same file.
+
+use std::path::Path;
+use std::str::FromStr;
+use super::Player;
+use super::board::{Board, Coord, Move};
+use super::book::{Book, BookMove};
+use super::mctsplayer::MCTSPlayer;
+
+/// Parameters of a book build, given on the command line after `book-build <file>`.
+#[derive(Clone, Debug)]
+pub struct BuildConfig {
+    /// Number of positions to add to the book.
+    pub expansions: usize,
+    /// How many of the most visited moves of a searched position are added.
+    pub width: usize,
+    /// How much the walk down the book favors moves with few visits over the best ones.
+    pub book_exploration: f32,
+    pub board_size: Coord,
+}
+
+impl BuildConfig {
+    pub fn new() -> BuildConfig {
+        BuildConfig {
+            expansions: 100,
+            width: 8,
+            book_exploration: 0.2,
+            board_size: 13,
+        }
+    }
+
+    /// List every parameter as a (name, value) pair.
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("expansions", self.expansions.to_string()),
+             ("width", self.width.to_string()),
+             ("book_exploration", self.book_exploration.to_string()),
+             ("board_size", self.board_size.to_string())]
+    }
+
+    /// Set a parameter by name, parsing the value from a string.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("invalid value for {}: {}", name, value))
+        }
+        match name {
+            "expansions" => self.expansions = try!(parse(name, value)),
+            "width" => {
+                let n = try!(parse(name, value));
+                if n == 0 {
+                    return Err("width must be at least 1".into());
+                }
+                self.width = n;
+            }
+            "book_exploration" => self.book_exploration = try!(parse(name, value)),
+            "board_size" => {
+                let n = try!(parse(name, value));
+                if n <= 0 {
+                    return Err(format!("invalid value for {}: {}", name, value));
+                }
+                self.board_size = n;
+            }
+            _ => return Err(format!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
+}
+
+/// Add `config.expansions` positions to the book at `path`, starting from what is already there,
+/// and save it after each one.
+pub fn build(player: &mut MCTSPlayer, config: &BuildConfig, path: &str) -> Result<(), String> {
+    let mut book = if Path::new(path).exists() {
+        try!(Book::load(path))
+    } else {
+        Book::new()
+    };
+    let root = Board::new((config.board_size, config.board_size));
+    for i in 0..config.expansions {
+        let mut board = root.snapshot();
+        let mut line = Vec::new();
+        if !select(&book, &mut board, &mut line, config.book_exploration) {
+            eprintln!("Every line of the book ends in a finished game");
+            break;
+        }
+        let names: Vec<String> = line.iter().map(|m| m.to_string()).collect();
+        eprintln!("Expansion {} of {}: {}",
+                  i + 1,
+                  config.expansions,
+                  if line.is_empty() { "empty board".into() } else { names.join(" ") });
+
+        if !expand(&mut book, player, &root, &line, config.width) {
+            return Err("the search found no moves".into());
+        }
+        back_up(&mut book, &root, &line);
+        try!(book.save(path));
+        let (value, visits) = value(&book, &root);
+        eprintln!("Book has {} positions, value {:.3} for {} with {} visits",
+                  book.len(),
+                  value,
+                  root.to_play(),
+                  visits);
+    }
+    Ok(())
+}
+
+/// The value of a book position for the player to move, which is the win rate of its best move,
+/// and the number of visits of all its moves.
+fn value(book: &Book, board: &Board) -> (f32, usize) {
+    let moves = book.moves(board);
+    let best = moves.iter().map(|e| e.win_rate()).fold(0.0, f32::max);
+    (best, moves.iter().map(|e| e.visits).sum())
+}
+
+/// Walk down the book to the position which should be expanded next, playing the moves on
+/// `board` and recording them in `line`. At each position the move with the best upper
+/// confidence bound is tried first, moving on to the next if every line below it ends in a
+/// finished game. Returns false if there is nothing left to expand.
+fn select(book: &Book, board: &mut Board, line: &mut Vec<Move>, exploration: f32) -> bool {
+    if board.winner().is_some() {
+        return false;
+    }
+    let mut moves = book.moves(board);
+    if moves.is_empty() {
+        return true;
+    }
+    let total = moves.iter().map(|e| e.visits).sum::<usize>().max(1) as f32;
+    let score = |e: &BookMove| {
+        e.win_rate() + exploration * (total.ln() / e.visits.max(1) as f32).sqrt()
+    };
+    moves.sort_by(|a, b| score(b).partial_cmp(&score(a)).unwrap());
+    for entry in moves {
+        if !board.make_move(entry.m) {
+            continue;
+        }
+        line.push(entry.m);
+        if select(book, board, line, exploration) {
+            return true;
+        }
+        line.pop();
+        board.undo();
+    }
+    false
+}
+
+/// Search the position after `line` and add its most visited moves to the book. Returns false if
+/// the search had no moves to offer.
+fn expand(book: &mut Book,
+          player: &mut MCTSPlayer,
+          root: &Board,
+          line: &[Move],
+          width: usize)
+          -> bool {
+    let dims = root.dimensions();
+    player.set_board_size(dims.x, dims.y);
+    for &m in line {
+        player.play_move(m);
+    }
+    let mut stats = player.analyze();
+    stats.retain(|s| s.visits > 0);
+    stats.sort_by(|a, b| b.visits.cmp(&a.visits));
+    let board = player.board().snapshot();
+    for s in stats.iter().take(width) {
+        // proven moves are worth exactly a win or a loss
+        let win_rate = match s.proven {
+            Some(true) => 1.0,
+            Some(false) => 0.0,
+            None => s.win_rate,
+        };
+        book.set(&board,
+                 BookMove {
+                     m: s.m,
+                     wins: win_rate * s.visits as f32,
+                     visits: s.visits,
+                 });
+    }
+    !stats.is_empty()
+}
+
+/// Update the moves of `line` from the position at its end back to the root: each is worth one
+/// minus the value of the position it leads to, and has all of that position's visits.
+fn back_up(book: &mut Book, root: &Board, line: &[Move]) {
+    let mut positions = vec![root.snapshot()];
+    for &m in line {
+        let mut next = positions.last().unwrap().snapshot();
+        next.make_move(m);
+        positions.push(next);
+    }
+    for (i, &m) in line.iter().enumerate().rev() {
+        let (value, visits) = value(book, &positions[i + 1]);
+        book.set(&positions[i],
+                 BookMove {
+                     m: m,
+                     wins: (1.0 - value) * visits as f32,
+                     visits: visits,
+                 });
+    }
+}
+
+#[test]
+fn test_build() {
+    use std::env;
+    use std::fs;
+    use super::mctsplayer::SearchConfig;
+
+    let mut search = SearchConfig::new();
+    search.num_threads = 1;
+    search.playouts = 300;
+    search.max_memory = 16;
+    let mut player = MCTSPlayer::new(search);
+    player.set_seed(1);
+    let mut config = BuildConfig::new();
+    config.board_size = 4;
+    config.expansions = 5;
+    config.width = 3;
+    let path = env::temp_dir().join(format!("coronene-test-{}.book", ::std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = fs::remove_file(path);
+
+    // a second run carries on from the first
+    build(&mut player, &config, path).unwrap();
+    assert_eq!(Book::load(path).unwrap().len(), 5);
+    build(&mut player, &config, path).unwrap();
+    let book = Book::load(path).unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(book.len(), 10);
+
+    // every move which leads to another book position has been backed up from it
+    let mut stack = vec![Board::new((4, 4))];
+    while let Some(board) = stack.pop() {
+        let moves = book.moves(&board);
+        assert!(moves.len() <= 3);
+        for entry in moves {
+            let mut next = board.snapshot();
+            assert!(next.make_move(entry.m));
+            if !book.moves(&next).is_empty() {
+                let (value, visits) = value(&book, &next);
+                assert_eq!(entry.visits, visits);
+                assert!((entry.win_rate() - (1.0 - value)).abs() < 1e-3);
+                stack.push(next);
+            }
+        }
+    }
+}
@@ -1,109 +1,358 @@
-use std::sync::{Arc, Weak};
-use super::misc::atomic_vec::AtomicInitVec;
+//! A search tree kept in one preallocated arena of nodes, linked to each other by index.
+//!
+//! Any number of threads may read the tree and add children to its leaves at the same time. A
+//! thread reserves slots for all of a node's children at once, fills them in while no other thread
+//! can see them, and then publishes them with a single atomic store to the parent. Only one set of
+//! children is ever published for a node; the slots reserved by a thread which loses the race are
+//! left unused until the next garbage collection. Moving or freeing nodes needs `&mut Tree`, so it
+//! can only happen once no search threads are running.
+
+use std::alloc::{self, Layout};
 use std::cell::UnsafeCell;
-use std::ops::{Deref, DerefMut};
+use std::mem::{self, MaybeUninit};
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// The index of a node in its tree.
+pub type NodeId = usize;
+
+/// The parent of a root node.
+const NO_PARENT: usize = usize::MAX;
+
+struct Slot<T> {
+    /// Initialized for every slot below `Tree::used`.
+    data: UnsafeCell<MaybeUninit<T>>,
+    parent: AtomicUsize,
+    /// The children of the node, as the index of the first one in the upper 32 bits and how many
+    /// there are in the lower 32 bits, or zero if it has none yet.
+    children: AtomicU64,
+}
 
-#[derive(Debug)]
-pub struct Node<T> {
-    data: T,
-    parent: Option<WeakNodeRef<T>>,
-    children: AtomicInitVec<NodeRef<T>>,
+pub struct Tree<T> {
+    /// All of the memory the tree may use. It is allocated zeroed, so the operating system only
+    /// provides pages as nodes are added to them.
+    slots: Box<[Slot<T>]>,
+    /// How many slots have been handed out.
+    used: AtomicUsize,
 }
 
-impl<T> Node<T> {
-    fn new(data: T) -> Node<T> {
-        Node {
-            data: data,
-            parent: None,
-            children: AtomicInitVec::new(),
+// nodes are only written by the thread which reserved them, before any other thread can see them
+unsafe impl<T: Send> Send for Tree<T> {}
+unsafe impl<T: Send + Sync> Sync for Tree<T> {}
+
+impl<T> Tree<T> {
+    /// Create a tree which can hold `capacity` nodes.
+    pub fn new(capacity: usize) -> Tree<T> {
+        let capacity = capacity.max(1).min(u32::max_value() as usize);
+        let layout = Layout::array::<Slot<T>>(capacity).expect("search tree too large");
+        let slots = unsafe {
+            // zeroed memory is a valid slot: the atomics are zero and the data is uninitialized
+            let ptr = alloc::alloc_zeroed(layout) as *mut Slot<T>;
+            if ptr.is_null() {
+                alloc::handle_alloc_error(layout);
+            }
+            Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, capacity))
+        };
+        Tree {
+            slots: slots,
+            used: AtomicUsize::new(0),
         }
     }
 
-    pub fn parent(&self) -> Option<&WeakNodeRef<T>> {
-        self.parent.as_ref()
+    /// Create a tree which uses at most `bytes` of memory.
+    pub fn with_memory(bytes: usize) -> Tree<T> {
+        Tree::new(bytes / Tree::<T>::node_size())
     }
 
-    pub fn children(&self) -> &mut [NodeRef<T>] {
-        self.children.slice()
+    /// The number of bytes of memory used by each node.
+    pub fn node_size() -> usize {
+        mem::size_of::<Slot<T>>()
     }
 
-    pub fn orphan(&mut self) {
-        self.parent = None;
+    /// The number of nodes the tree can hold.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
     }
 
-    pub fn tree_size(&self) -> usize {
-        let mut sum = 0;
-        for child in self.children() {
-            sum += 1 + child.tree_size();
+    /// The number of nodes allocated, including any which are no longer reachable.
+    pub fn len(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    pub fn get<'a>(&'a self, id: NodeId) -> NodeRef<'a, T> {
+        debug_assert!(id < self.len());
+        NodeRef {
+            tree: self,
+            id: id,
         }
-        sum
     }
-}
 
-impl<T> Deref for Node<T> {
-    type Target = T;
-    fn deref(&self) -> &Self::Target {
-        &self.data
+    /// Add a node without a parent. Returns `None` if the tree is full.
+    pub fn add_root(&self, data: T) -> Option<NodeId> {
+        self.reserve(1).map(|id| {
+            self.init(id, data, NO_PARENT);
+            id
+        })
+    }
+
+    /// Give a node its children, unless it already has some or the tree is full. Returns true if
+    /// the children were added.
+    pub fn add_children(&self, parent: NodeId, children: Vec<T>) -> bool {
+        let count = children.len();
+        if count == 0 || self.get(parent).has_children() {
+            return false;
+        }
+        let first = match self.reserve(count) {
+            Some(first) => first,
+            None => return false,
+        };
+        for (i, data) in children.into_iter().enumerate() {
+            self.init(first + i, data, parent);
+        }
+        let packed = (first as u64) << 32 | count as u64;
+        self.slots[parent]
+            .children
+            .compare_exchange(0, packed, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Keep only the nodes which can be reached from `roots`, moving them to the start of the
+    /// arena so that the rest of it can be reused. The ids in `roots` are updated to where their
+    /// nodes have moved, and nodes whose parent is freed become roots. Every other id becomes
+    /// invalid.
+    pub fn collect(&mut self, roots: &mut [NodeId]) {
+        let used = *self.used.get_mut();
+        let mut reachable = vec![false; used];
+        let mut stack: Vec<NodeId> = roots.to_vec();
+        for &root in roots.iter() {
+            reachable[root] = true;
+        }
+        while let Some(id) = stack.pop() {
+            let (first, count) = unpack(*self.slots[id].children.get_mut());
+            for child in first..first + count {
+                reachable[child] = true;
+                stack.push(child);
+            }
+        }
+
+        // slide the reachable nodes down, keeping them in order, so that each one moves to a slot
+        // which has already been freed or moved out of
+        let mut forward = vec![NO_PARENT; used];
+        let mut next = 0;
+        for id in 0..used {
+            if reachable[id] {
+                forward[id] = next;
+                next += 1;
+            }
+        }
+        for id in 0..used {
+            if !reachable[id] {
+                unsafe {
+                    ptr::drop_in_place((*self.slots[id].data.get()).as_mut_ptr());
+                }
+                continue;
+            }
+            let parent = *self.slots[id].parent.get_mut();
+            let (first, count) = unpack(*self.slots[id].children.get_mut());
+            self.slots.swap(id, forward[id]);
+            let slot = &mut self.slots[forward[id]];
+            *slot.parent.get_mut() = if parent != NO_PARENT && reachable[parent] {
+                forward[parent]
+            } else {
+                NO_PARENT
+            };
+            if count > 0 {
+                *slot.children.get_mut() = (forward[first] as u64) << 32 | count as u64;
+            }
+        }
+        *self.used.get_mut() = next;
+        for root in roots.iter_mut() {
+            *root = forward[*root];
+        }
+    }
+
+    /// Make a node a leaf again. Its children are freed by the next `collect`.
+    pub fn remove_children(&mut self, id: NodeId) {
+        *self.slots[id].children.get_mut() = 0;
+    }
+
+    /// Remove every node.
+    pub fn clear(&mut self) {
+        self.collect(&mut []);
+    }
+
+    /// Hand out `count` consecutive unused slots.
+    fn reserve(&self, count: usize) -> Option<NodeId> {
+        let mut used = self.used.load(Ordering::SeqCst);
+        loop {
+            if used + count > self.capacity() {
+                return None;
+            }
+            let reserved = self.used.compare_exchange_weak(used,
+                                                           used + count,
+                                                           Ordering::SeqCst,
+                                                           Ordering::SeqCst);
+            match reserved {
+                Ok(_) => return Some(used),
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    /// Fill in a slot reserved by this thread, which no other thread can see yet.
+    fn init(&self, id: NodeId, data: T, parent: NodeId) {
+        let slot = &self.slots[id];
+        unsafe {
+            (*slot.data.get()).as_mut_ptr().write(data);
+        }
+        slot.parent.store(parent, Ordering::Relaxed);
+        slot.children.store(0, Ordering::Relaxed);
     }
 }
 
-impl<T> DerefMut for Node<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.data
+impl<T> Drop for Tree<T> {
+    fn drop(&mut self) {
+        self.clear();
     }
 }
 
-#[derive(Debug)]
-pub struct NodeRef<T>(Arc<UnsafeCell<Node<T>>>);
-#[derive(Debug)]
-pub struct WeakNodeRef<T>(Weak<UnsafeCell<Node<T>>>);
+fn unpack(children: u64) -> (NodeId, usize) {
+    ((children >> 32) as NodeId, (children & 0xffff_ffff) as usize)
+}
 
-unsafe impl<T> Send for NodeRef<T> { }
-unsafe impl<T> Send for WeakNodeRef<T> { }
+/// A node of a tree, which dereferences to the data stored in it.
+pub struct NodeRef<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    id: NodeId,
+}
 
-impl<T> Clone for NodeRef<T> {
+impl<'a, T> Clone for NodeRef<'a, T> {
     fn clone(&self) -> Self {
-        NodeRef(self.0.clone())
+        *self
     }
 }
-impl<T> Clone for WeakNodeRef<T> {
-    fn clone(&self) -> Self {
-        WeakNodeRef(self.0.clone())
+
+impl<'a, T> Copy for NodeRef<'a, T> {}
+
+impl<'a, T> NodeRef<'a, T> {
+    pub fn id(&self) -> NodeId {
+        self.id
     }
-}
 
-impl<T> NodeRef<T> {
-    pub fn new(data: T) -> NodeRef<T> {
-        NodeRef(Arc::new(UnsafeCell::new(Node::new(data))))
+    pub fn parent(&self) -> Option<NodeRef<'a, T>> {
+        match self.tree.slots[self.id].parent.load(Ordering::Relaxed) {
+            NO_PARENT => None,
+            parent => Some(self.tree.get(parent)),
+        }
+    }
+
+    pub fn has_children(&self) -> bool {
+        self.tree.slots[self.id].children.load(Ordering::Acquire) != 0
     }
 
-    pub fn add_children(&self, mut children: Vec<NodeRef<T>>) -> bool {
-        for child in &mut children {
-            child.parent = Some(WeakNodeRef(Arc::downgrade(&self.0.clone())));
+    pub fn children(&self) -> Children<'a, T> {
+        let (first, count) = unpack(self.tree.slots[self.id].children.load(Ordering::Acquire));
+        Children {
+            tree: self.tree,
+            next: first,
+            end: first + count,
         }
-        self.children.init(children)
+    }
+
+    /// The number of nodes below this one.
+    pub fn tree_size(&self) -> usize {
+        self.children().map(|child| 1 + child.tree_size()).sum()
+    }
+
+    /// The length of the longest path from this node down to a leaf.
+    pub fn tree_depth(&self) -> usize {
+        self.children().map(|child| 1 + child.tree_depth()).max().unwrap_or(0)
     }
 }
 
-impl<T> WeakNodeRef<T> {
-    pub fn upgrade(&self) -> NodeRef<T> {
-        NodeRef(self.0.upgrade().unwrap())
+impl<'a, T> Deref for NodeRef<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*(*self.tree.slots[self.id].data.get()).as_ptr() }
     }
 }
 
-impl<T> Deref for NodeRef<T> {
-    type Target = Node<T>;
-    fn deref(&self) -> &Self::Target {
-        unsafe {
-            &*self.0.get()
+/// The children of a node, in the order they were added.
+pub struct Children<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    next: NodeId,
+    end: NodeId,
+}
+
+impl<'a, T> Clone for Children<'a, T> {
+    fn clone(&self) -> Self {
+        Children {
+            tree: self.tree,
+            next: self.next,
+            end: self.end,
         }
     }
 }
 
-impl<T> DerefMut for NodeRef<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe {
-            &mut *self.0.get()
+impl<'a, T> Iterator for Children<'a, T> {
+    type Item = NodeRef<'a, T>;
+
+    fn next(&mut self) -> Option<NodeRef<'a, T>> {
+        if self.next == self.end {
+            return None;
         }
+        self.next += 1;
+        Some(self.tree.get(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
     }
 }
+
+impl<'a, T> ExactSizeIterator for Children<'a, T> {}
+
+#[test]
+fn test_tree() {
+    use std::rc::Rc;
+
+    // count live values, to check that everything freed is dropped exactly once
+    let live = Rc::new(());
+    let mut tree = Tree::new(10);
+    let root = tree.add_root((0, live.clone())).unwrap();
+    assert!(tree.add_children(root, (1..4).map(|i| (i, live.clone())).collect()));
+    assert!(!tree.add_children(root, vec![(9, live.clone())]));
+    let child = tree.get(root).children().nth(1).unwrap().id();
+    assert!(tree.add_children(child, (4..6).map(|i| (i, live.clone())).collect()));
+    assert!(!tree.add_children(child + 1, (6..12).map(|i| (i, live.clone())).collect()));
+    assert_eq!(tree.get(root).tree_size(), 5);
+    assert_eq!(tree.get(root).tree_depth(), 2);
+    assert_eq!(tree.len(), 6);
+    assert_eq!(Rc::strong_count(&live), 7);
+
+    // re-rooting at the child frees its siblings and old parent
+    let mut roots = [child];
+    tree.collect(&mut roots);
+    let new_root = tree.get(roots[0]);
+    assert_eq!(new_root.0, 2);
+    assert!(new_root.parent().is_none());
+    assert_eq!(new_root.children().map(|c| c.0).collect::<Vec<_>>(), vec![4, 5]);
+    assert!(new_root.children().all(|c| c.parent().unwrap().id() == new_root.id()));
+    assert_eq!(tree.len(), 3);
+    assert_eq!(Rc::strong_count(&live), 4);
+
+    // pruning makes the root a leaf again
+    let root = roots[0];
+    tree.remove_children(root);
+    tree.collect(&mut roots);
+    assert!(!tree.get(roots[0]).has_children());
+    assert_eq!(tree.len(), 1);
+    assert_eq!(Rc::strong_count(&live), 2);
+    assert!(tree.add_children(roots[0], vec![(6, live.clone())]));
+
+    tree.clear();
+    assert_eq!(tree.len(), 0);
+    assert_eq!(Rc::strong_count(&live), 1);
+}
@@ -1,8 +1,24 @@
 use std::io::{BufRead, Write};
-use super::board::{Move, Pos, Coord};
-use super::Player;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use super::board::{Move, Coord, Color, Pos};
+use super::timecontrol::{TimeSettings, Clock};
+use super::sgf::Game;
+use super::solver::Solution;
+use super::book::BookMove;
+use super::vc::{Point, VcSet};
+use super::{Player, MoveStats};
 use std::fmt::Display;
 
+/// Analyze commands understood by HexGui, as `type/label/command`.
+const ANALYZE_COMMANDS: &'static [&'static str] = &["pspairs/MCTS Visits/mcts-visits",
+                                                    "pspairs/MCTS Win Rates/mcts-winrates",
+                                                    "pspairs/MCTS RAVE Win Rates/mcts-rave",
+                                                    "pspairs/MCTS Proven Moves/mcts-proven",
+                                                    "var/MCTS Principal Variation/mcts-pv",
+                                                    "pspairs/Book Win Rates/book-scores",
+                                                    "string/MCTS Best Moves/mcts-best"];
+
 // like try!, but it sends the error over htp and continues to the next command
 macro_rules! try_htp {
     ($htp:ident, $e:expr) => {
@@ -16,21 +32,100 @@ macro_rules! try_htp {
     };
 }
 
-pub struct HTP<R, W>
-    where R: BufRead,
-          W: Write
+fn parse_size(size: &str) -> Result<Coord, &'static str> {
+    match size.parse::<Coord>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err("invalid size"),
+    }
+}
+
+/// Format a value for each cell in HexGui's pspairs format, e.g. `a1 0.52 b3 0.47`. Moves which
+/// are not cells, such as swaps, are left out.
+fn pspairs<F>(stats: &[MoveStats], value: F) -> String
+    where F: Fn(&MoveStats) -> Option<String>
 {
-    input: R,
+    let mut out = Vec::new();
+    for s in stats {
+        if let Move::Play { pos, .. } = s.m {
+            if let Some(value) = value(s) {
+                out.push(format!("{} {}", pos, value));
+            }
+        }
+    }
+    out.join(" ")
+}
+
+/// Format the carriers of virtual connections, one per line.
+fn carriers(carriers: &[Vec<Pos>]) -> String {
+    let lines: Vec<String> = carriers.iter()
+                                     .map(|c| {
+                                         let cells: Vec<String> =
+                                             c.iter().map(|pos| pos.to_string()).collect();
+                                         cells.join(" ")
+                                     })
+                                     .collect();
+    lines.join("\n")
+}
+
+/// Set up the player with a game from an SGF file. As in GTP, if `move_number` is given, the
+/// position is set up just before that move is played.
+fn load_sgf<P: Player>(player: &mut P,
+                       path: &str,
+                       move_number: Option<usize>)
+                       -> Result<(), String> {
+    let game = try!(Game::load(path));
+    let num_moves = move_number.map_or(game.moves.len(), |n| n.saturating_sub(1));
+    // check the record before touching the player, so a bad file leaves the game as it was
+    try!(game.board(num_moves));
+    player.set_board_size(game.dims.x, game.dims.y);
+    if !player.set_up(&game.setup) {
+        return Err("invalid setup stones in game record".into());
+    }
+    for &m in game.moves.iter().take(num_moves) {
+        if !player.play_move(m) {
+            return Err(format!("illegal move in game record: {}", m));
+        }
+    }
+    Ok(())
+}
+
+/// Save the player's game, with its setup stones, to an SGF file.
+fn save_sgf<P: Player>(player: &P, path: &str) -> Result<(), String> {
+    let mut game = Game::new(player.board().dimensions(), player.moves().to_vec());
+    game.setup = player.setup().to_vec();
+    game.save(path)
+}
+
+pub struct HTP<W>
+    where W: Write
+{
+    /// Lines of input, read by a separate thread so that the player can ponder while waiting.
+    input: Receiver<String>,
     output: W,
 }
 
-impl<R, W> HTP<R, W>
-    where R: BufRead,
-          W: Write
+impl<W> HTP<W>
+    where W: Write
 {
-    pub fn new(input: R, output: W) -> HTP<R, W> {
+    pub fn new<R>(mut input: R, output: W) -> HTP<W>
+        where R: BufRead + Send + 'static
+    {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                let mut buf = String::new();
+                match input.read_line(&mut buf) {
+                    Ok(n) if n > 0 => {
+                        if tx.send(buf).is_err() {
+                            break;
+                        }
+                    }
+                    _ => break,
+                }
+            }
+        });
         HTP {
-            input: input,
+            input: rx,
             output: output,
         }
     }
@@ -39,7 +134,7 @@ impl<R, W> HTP<R, W>
         where P: Player
     {
         loop {
-            let cmd_str = match self.read() {
+            let cmd_str = match self.read(&mut player) {
                 Some(x) => x,
                 None => break,
             };
@@ -49,14 +144,9 @@ impl<R, W> HTP<R, W>
                     let color = try_htp!(self, color.parse().map_err(|_| "invalid color"));
                     self.write_ok(format!("{}", player.generate_move(color)))
                 }
-                ["play", color, pos] => {
-                    let m = if pos == "resign" {
-                        Move::Resign
-                    } else {
-                        let color = try_htp!(self, color.parse().map_err(|_| "invalid color"));
-                        let pos = try_htp!(self, pos.parse::<Pos>().map_err(|_| "invalid move"));
-                        Move::new(color, pos)
-                    };
+                ["play", color, m] => {
+                    let color = try_htp!(self, color.parse().map_err(|_| "invalid color"));
+                    let m = try_htp!(self, Move::parse(color, m).map_err(|_| "invalid move"));
                     if player.play_move(m) {
                         self.write_ok("")
                     } else {
@@ -70,19 +160,250 @@ impl<R, W> HTP<R, W>
                 ["showboard"] => self.write_ok(player.board()),
                 ["name"] => self.write_ok(player.name()),
                 ["version"] => self.write_ok(player.version()),
-                ["hexgui-analyze_commands"] => self.write_ok(""),
+                ["hexgui-analyze_commands"] => self.write_ok(ANALYZE_COMMANDS.join("\n")),
+                ["mcts-visits"] => {
+                    let stats = player.move_stats();
+                    self.write_ok(pspairs(&stats, |s| Some(s.visits.to_string())))
+                }
+                ["mcts-winrates"] => {
+                    let stats = player.move_stats();
+                    self.write_ok(pspairs(&stats, |s| {
+                        if s.visits > 0 {
+                            Some(format!("{:.3}", s.win_rate))
+                        } else {
+                            None
+                        }
+                    }))
+                }
+                ["mcts-rave"] => {
+                    let stats = player.move_stats();
+                    self.write_ok(pspairs(&stats, |s| {
+                        if s.rave_visits > 0 {
+                            Some(format!("{:.3}", s.rave_win_rate))
+                        } else {
+                            None
+                        }
+                    }))
+                }
+                ["mcts-proven"] => {
+                    let stats = player.move_stats();
+                    self.write_ok(pspairs(&stats, |s| {
+                        s.proven.map(|win| if win { "win" } else { "loss" }.to_string())
+                    }))
+                }
+                ["mcts-pv"] => {
+                    let pv: Vec<_> = player.principal_variation()
+                                           .iter()
+                                           .filter_map(|m| {
+                                               m.color().map(|c| format!("{} {}", c, m))
+                                           })
+                                           .collect();
+                    self.write_ok(pv.join(" "))
+                }
+                ["mcts-best"] => {
+                    let mut stats = player.move_stats();
+                    stats.sort_by(|a, b| b.visits.cmp(&a.visits));
+                    let mut out = String::new();
+                    for s in stats.iter().take(10).filter(|s| s.visits > 0) {
+                        out.push_str(&format!("\n{} visits {} win {:.3} rave {:.3} ({})",
+                                              s.m,
+                                              s.visits,
+                                              s.win_rate,
+                                              s.rave_win_rate,
+                                              s.rave_visits));
+                    }
+                    self.write_ok(out)
+                }
+                ["boardsize", size] => {
+                    let size = try_htp!(self, parse_size(size));
+                    player.set_board_size(size, size);
+                    self.write_ok("")
+                }
                 ["boardsize", cols, rows] => {
-                    let cols = try_htp!(self, cols.parse::<Coord>().map_err(|_| "invalid size"));
-                    let rows = try_htp!(self, rows.parse::<Coord>().map_err(|_| "invalid size"));
+                    let cols = try_htp!(self, parse_size(cols));
+                    let rows = try_htp!(self, parse_size(rows));
                     player.set_board_size(cols, rows);
                     self.write_ok("")
                 }
+                ["param_mcts"] => {
+                    let mut out = String::new();
+                    for (name, value) in player.params() {
+                        out.push_str(&format!("\n[string] {} {}", name, value));
+                    }
+                    self.write_ok(out)
+                }
+                ["param_mcts", name, value] => {
+                    try_htp!(self, player.set_param(name, value));
+                    self.write_ok("")
+                }
+                ["time_settings", main_time, byoyomi_time, byoyomi_stones] => {
+                    let settings = TimeSettings {
+                        main_time: try_htp!(self, main_time.parse().map_err(|_| "invalid time")),
+                        byoyomi_time: try_htp!(self,
+                                               byoyomi_time.parse().map_err(|_| "invalid time")),
+                        byoyomi_stones: try_htp!(self,
+                                                 byoyomi_stones.parse()
+                                                               .map_err(|_| "invalid stones")),
+                    };
+                    player.time_control().set_settings(settings);
+                    self.write_ok("")
+                }
+                ["time_left", color, time, stones] => {
+                    let color = try_htp!(self, color.parse().map_err(|_| "invalid color"));
+                    let clock = Clock {
+                        time: try_htp!(self, time.parse().map_err(|_| "invalid time")),
+                        stones: try_htp!(self, stones.parse().map_err(|_| "invalid stones")),
+                    };
+                    player.time_control().set_clock(color, clock);
+                    self.write_ok("")
+                }
+                ["time_left"] => {
+                    let (black, white) = {
+                        let tc = player.time_control();
+                        (tc.clock(Color::Black), tc.clock(Color::White))
+                    };
+                    self.write_ok(format!("B {:.1} {} W {:.1} {}",
+                                          black.time,
+                                          black.stones,
+                                          white.time,
+                                          white.stones))
+                }
+                ["set_random_seed", seed] => {
+                    let seed = try_htp!(self, seed.parse().map_err(|_| "invalid seed"));
+                    player.set_seed(seed);
+                    self.write_ok("")
+                }
+                ["loadsgf", path] => {
+                    try_htp!(self, load_sgf(&mut player, path, None));
+                    self.write_ok("")
+                }
+                ["loadsgf", path, move_number] => {
+                    let move_number = try_htp!(self,
+                                               move_number.parse()
+                                                          .map_err(|_| "invalid move number"));
+                    try_htp!(self, load_sgf(&mut player, path, Some(move_number)));
+                    self.write_ok("")
+                }
+                ["savesgf", path] => {
+                    try_htp!(self, save_sgf(&player, path));
+                    self.write_ok("")
+                }
+                ["ponder", toggle] => {
+                    let enabled = match toggle {
+                        "on" => "1",
+                        "off" => "0",
+                        _ => try_htp!(self, Err("expected on or off")),
+                    };
+                    try_htp!(self, player.set_param("ponder", enabled));
+                    self.write_ok("")
+                }
+                ["search_info"] => self.write_ok(player.search_info()),
+                ["dfpn-solve-state", color] => {
+                    let color = try_htp!(self, color.parse().map_err(|_| "invalid color"));
+                    match player.solve(color) {
+                        Some(Solution { winner, best_move: Some(m) }) => {
+                            self.write_ok(format!("{} {}", winner, m))
+                        }
+                        Some(Solution { winner, best_move: None }) => self.write_ok(winner),
+                        None => self.write_ok("unknown"),
+                    }
+                }
+                [cmd @ "vc-between-cells-full", color, a, b] |
+                [cmd @ "vc-between-cells-semi", color, a, b] => {
+                    let color = try_htp!(self, color.parse().map_err(|_| "invalid color"));
+                    let a = try_htp!(self, a.parse::<Pos>().map_err(|_| "invalid cell"));
+                    let b = try_htp!(self, b.parse::<Pos>().map_err(|_| "invalid cell"));
+                    let vcs = VcSet::new(player.board(), color);
+                    let found = if cmd == "vc-between-cells-full" {
+                        vcs.full_connections(Point::Cell(a), Point::Cell(b))
+                    } else {
+                        vcs.semi_connections(Point::Cell(a), Point::Cell(b))
+                    };
+                    self.write_ok(carriers(&found))
+                }
+                ["book-load", path] => {
+                    try_htp!(self, player.set_param("book", path));
+                    self.write_ok("")
+                }
+                ["book-save", path] => {
+                    try_htp!(self, player.book().save(path));
+                    self.write_ok("")
+                }
+                ["book-clear"] => {
+                    player.book().clear();
+                    self.write_ok("")
+                }
+                ["book-moves"] => {
+                    // tell an empty book apart from a position the book doesn't know
+                    if player.book().is_empty() {
+                        try_htp!(self, Err("the book is empty"));
+                    }
+                    let board = player.board().snapshot();
+                    let mut moves = player.book().moves(&board);
+                    moves.sort_by(|a, b| b.visits.cmp(&a.visits));
+                    let lines: Vec<String> = moves.iter()
+                                                  .map(|e| {
+                                                      format!("{} {:.3} {}",
+                                                              e.m,
+                                                              e.win_rate(),
+                                                              e.visits)
+                                                  })
+                                                  .collect();
+                    self.write_ok(lines.join("\n"))
+                }
+                ["book-scores"] => {
+                    let board = player.board().snapshot();
+                    let moves = player.book().moves(&board);
+                    let pairs: Vec<String> = moves.iter()
+                                                  .filter_map(|e| {
+                                                      e.m.pos().map(|pos| {
+                                                          format!("{} {:.3}", pos, e.win_rate())
+                                                      })
+                                                  })
+                                                  .collect();
+                    self.write_ok(pairs.join(" "))
+                }
+                ["book-set", m, wins, visits] => {
+                    let board = player.board().snapshot();
+                    let m = try_htp!(self,
+                                     Move::parse(board.to_play(), m).map_err(|_| "invalid move"));
+                    let wins = try_htp!(self, wins.parse().map_err(|_| "invalid wins"));
+                    let visits = try_htp!(self, visits.parse().map_err(|_| "invalid visits"));
+                    let mut next = board.snapshot();
+                    if !next.make_move(m) {
+                        try_htp!(self, Err("invalid move"));
+                    }
+                    player.book().set(&board,
+                                      BookMove {
+                                          m: m,
+                                          wins: wins,
+                                          visits: visits,
+                                      });
+                    self.write_ok("")
+                }
+                ["book-remove", m] => {
+                    let board = player.board().snapshot();
+                    let m = try_htp!(self,
+                                     Move::parse(board.to_play(), m).map_err(|_| "invalid move"));
+                    if player.book().remove(&board, m) {
+                        self.write_ok("")
+                    } else {
+                        self.write_err("move is not in the book")
+                    }
+                }
                 ["quit"] => {
                     self.write_ok("");
                     break;
                 }
                 ["final_score"] => {
-                    if let Some(color) = player.board().winner() {
+                    // a player resigns on their turn, so after a resignation the other player wins
+                    let resigned = player.moves().last() == Some(&Move::Resign);
+                    let winner = player.board().winner().or(if resigned {
+                        Some(player.board().to_play().invert())
+                    } else {
+                        None
+                    });
+                    if let Some(color) = winner {
                         self.write_ok(color);
                     } else {
                         self.write_err("game is not finished!");
@@ -93,12 +414,19 @@ impl<R, W> HTP<R, W>
         }
     }
 
-    fn read(&mut self) -> Option<String> {
-        let mut buf = String::new();
-        let res = self.input.read_line(&mut buf);
-        match res {
-            Ok(n) if n > 0 => Some(buf),
-            _ => None,
+    /// Wait for the next command, letting the player ponder in the meantime.
+    fn read<P>(&mut self, player: &mut P) -> Option<String>
+        where P: Player
+    {
+        match self.input.try_recv() {
+            Ok(line) => Some(line),
+            Err(TryRecvError::Empty) => {
+                player.start_pondering();
+                let line = self.input.recv().ok();
+                player.stop_pondering();
+                line
+            }
+            Err(TryRecvError::Disconnected) => None,
         }
     }
 
@@ -113,3 +441,42 @@ impl<R, W> HTP<R, W>
         write!(self.output, "? {}\n\n", msg).unwrap()
     }
 }
+
+#[test]
+fn test_sgf_commands() {
+    use std::env;
+    use std::fs::{self, File};
+    use super::mctsplayer::{MCTSPlayer, SearchConfig};
+
+    let path = |name: &str| {
+        let file = format!("coronene-test-{}-{}.sgf", ::std::process::id(), name);
+        env::temp_dir().join(file).to_str().unwrap().to_string()
+    };
+    let (input, output) = (path("in"), path("out"));
+    File::create(&input)
+        .and_then(|mut f| f.write_all(b"(;FF[4]GM[11]SZ[5]AB[a1][b2]AW[c3];B[d4];W[e5])"))
+        .unwrap();
+    let mut config = SearchConfig::new();
+    config.num_threads = 1;
+    config.max_memory = 16;
+    let mut player = MCTSPlayer::new(config);
+
+    // setup stones are not moves, so they don't pass the turn and can't be taken back
+    load_sgf(&mut player, &input, Some(2)).unwrap();
+    assert_eq!(player.moves().len(), 1);
+    assert_eq!(player.board().to_play(), Color::White);
+    player.undo();
+    player.undo();
+    assert_eq!(player.moves().len(), 0);
+    assert_eq!(player.board().iter_filled().count(), 3);
+    assert_eq!(player.board().to_play(), Color::Black);
+
+    // saving gives back the same record
+    load_sgf(&mut player, &input, None).unwrap();
+    save_sgf(&player, &output).unwrap();
+    let (saved, original) = (Game::load(&output), Game::load(&input));
+    fs::remove_file(&input).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert_eq!(saved, original);
+    assert_eq!(saved.unwrap().setup.len(), 3);
+}
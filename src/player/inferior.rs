@@ -0,0 +1,233 @@
+//! Inferior cell analysis: finding empty cells which need not be considered by the search.
+//!
+//! A player's stone in a cell is useless if any chain through it can be rerouted through the
+//! player's stones around it. This can be seen locally: the cells around it which the player could
+//! pass through (their own stones, edges and empty cells) must form a single arc on the ring of
+//! neighbors, with only the player's stones between its ends. A cell which is useless to both
+//! players is dead, and can be filled with either color without changing the outcome.
+//!
+//! - Two adjacent empty cells are captured by a player if, whichever one the opponent takes, the
+//!   player can answer with the other and make the opponent's stone dead. They can be filled with
+//!   the player's stones.
+//! - A cell is vulnerable if an opponent stone in a neighboring cell, its killer, would make it
+//!   dead. The player to move does not need to play there, as long as the killer is considered.
+
+use std::collections::HashSet;
+use super::board::{Board, Color, Pos};
+
+/// The neighbors of a cell, in order around it.
+const NEIGHBORS: [(i16, i16); 6] = [(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)];
+
+/// The inferior cells of a position.
+#[derive(Clone, Debug)]
+pub struct InferiorCells {
+    /// Cells which can be filled without changing the outcome, and the color to fill them with.
+    /// Dead cells are filled with the opponent of the player to move.
+    pub fill: Vec<(Pos, Color)>,
+    /// Cells which the player to move need not play, with the killer which makes each of them
+    /// dead. No cell is the killer of another vulnerable cell.
+    pub vulnerable: Vec<(Pos, Pos)>,
+}
+
+impl InferiorCells {
+    /// Analyze a position from the point of view of the player to move.
+    pub fn new(board: &Board) -> InferiorCells {
+        let mut filled = board.snapshot();
+        let fill = fill_in(&mut filled);
+
+        // vulnerable cells may only be pruned if their killers are not
+        let opponent = board.to_play().invert();
+        let mut vulnerable = Vec::new();
+        let mut killers = HashSet::new();
+        for pos in filled.iter_empty() {
+            for &pat in NEIGHBORS.iter() {
+                let killer = pos + pat.into();
+                if !filled.on_board(killer) || !filled.is_empty(killer) ||
+                   killers.contains(&pos) ||
+                   vulnerable.iter().any(|&(p, _)| p == killer) {
+                    continue;
+                }
+                if is_dead(&filled, pos, Some((killer, opponent))) {
+                    vulnerable.push((pos, killer));
+                    killers.insert(killer);
+                    break;
+                }
+            }
+        }
+
+        InferiorCells {
+            fill: fill,
+            vulnerable: vulnerable,
+        }
+    }
+
+    /// Whether the player to move can leave out a cell.
+    pub fn is_inferior(&self, pos: Pos) -> bool {
+        self.fill.iter().any(|&(p, _)| p == pos) || self.vulnerable.iter().any(|&(p, _)| p == pos)
+    }
+}
+
+/// Fill in dead and captured cells until there are none left, without changing the player to move.
+/// Returns the cells filled, in order, with their colors.
+pub fn fill_in(board: &mut Board) -> Vec<(Pos, Color)> {
+    let dead_color = board.to_play().invert();
+    let mut filled = Vec::new();
+    loop {
+        let start = filled.len();
+        let empty: Vec<Pos> = board.iter_empty().collect();
+        for &pos in &empty {
+            // a captured pair leaves at most two empty neighbors once one of them is filled
+            if !board.is_empty(pos) || empty_neighbors(board, pos) > 3 {
+                continue;
+            }
+            if is_dead(board, pos, None) {
+                board.set(pos, Some(dead_color));
+                filled.push((pos, dead_color));
+                continue;
+            }
+            // only look at half of the neighbors, since each pair is seen from both of its cells
+            for &pat in NEIGHBORS[..3].iter() {
+                let other = pos + pat.into();
+                if !board.on_board(other) || !board.is_empty(other) {
+                    continue;
+                }
+                let captor = [Color::Black, Color::White].iter().cloned().find(|&color| {
+                    is_dead(board, pos, Some((other, color))) &&
+                    is_dead(board, other, Some((pos, color)))
+                });
+                if let Some(color) = captor {
+                    board.set(pos, Some(color));
+                    board.set(other, Some(color));
+                    filled.push((pos, color));
+                    filled.push((other, color));
+                    break;
+                }
+            }
+        }
+        if filled.len() == start || board.winner().is_some() {
+            return filled;
+        }
+    }
+}
+
+/// Check whether the color of an empty cell cannot matter, pretending that the cell `assume` has
+/// been filled in.
+fn is_dead(board: &Board, pos: Pos, assume: Option<(Pos, Color)>) -> bool {
+    let assumed = assume.map_or(0, |(p, _)| if board.is_empty(p) { 1 } else { 0 });
+    // empty neighbors can only be at the ends of the arcs of both players, so there can be at
+    // most two, which rules out most cells cheaply
+    empty_neighbors(board, pos) - assumed <= 2 && is_useless(board, pos, Color::Black, assume) &&
+    is_useless(board, pos, Color::White, assume)
+}
+
+fn empty_neighbors(board: &Board, pos: Pos) -> usize {
+    NEIGHBORS.iter()
+             .map(|&pat| pos + pat.into())
+             .filter(|&n| board.on_board(n) && board.is_empty(n))
+             .count()
+}
+
+/// Check whether a stone of `color` in an empty cell could never be needed to connect.
+fn is_useless(board: &Board, pos: Pos, color: Color, assume: Option<(Pos, Color)>) -> bool {
+    // for each neighbor, whether the player could pass through it and whether it is theirs
+    let mut passable = [false; 6];
+    let mut stone = [false; 6];
+    for (i, &pat) in NEIGHBORS.iter().enumerate() {
+        let n = pos + pat.into();
+        let content = match assume {
+            Some((p, c)) if p == n => Some(c),
+            _ => board.get(n),
+        };
+        stone[i] = content == Some(color);
+        // off board positions which are not an edge are beyond a corner
+        passable[i] = stone[i] || (content.is_none() && board.on_board(n));
+    }
+
+    let empty = (0..6).filter(|&i| passable[i] && !stone[i]).count();
+    let start = match (0..6).find(|&i| !passable[i]) {
+        Some(i) => i,
+        // any two empty neighbors are joined by stones going one way or the other around the ring
+        None => return empty <= 2,
+    };
+    // walk around the ring from a cell which can't be passed, so that no arc wraps around
+    let mut arcs = 0;
+    let mut i = start;
+    for _ in 0..6 {
+        i = (i + 1) % 6;
+        if !passable[i] {
+            continue;
+        }
+        let first = !passable[(i + 5) % 6];
+        let last = !passable[(i + 1) % 6];
+        if first {
+            arcs += 1;
+        }
+        // a cell inside the arc must be a stone, or chains could pass the cell through it
+        if !first && !last && !stone[i] {
+            return false;
+        }
+    }
+    arcs <= 1
+}
+
+#[test]
+fn test_inferior_cells() {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use super::board::{solve, Move};
+
+    // a cell with four neighbors of one color is dead
+    let mut board = Board::new((5, 5));
+    for &pos in &[(1, 2), (2, 1), (3, 1), (3, 2)] {
+        board.play(Move::new(Color::Black, pos));
+    }
+    assert!(is_dead(&board, Pos::new(2, 2), None));
+    assert!(!is_dead(&board, Pos::new(1, 3), None));
+
+    // a cell in the obtuse corner between two opponent stones is captured along with its neighbor
+    let mut board = Board::new((5, 5));
+    board.play(Move::new(Color::White, (0, 1)));
+    board.play(Move::new(Color::Black, (2, 0)));
+    board.set_to_play(Color::Black);
+    let inferior = InferiorCells::new(&board);
+    assert!(inferior.is_inferior(Pos::new(0, 0)));
+
+    // pruning inferior cells never loses a win, and filling them in never changes the winner
+    let mut rng = XorShiftRng::from_seed([8, 7, 6, 5]);
+    for _ in 0..200 {
+        let mut board = Board::new((4, 4));
+        let mut cells: Vec<Pos> = board.iter_empty().collect();
+        rng.shuffle(&mut cells);
+        let stones = rng.gen_range(6, 10);
+        for (i, &pos) in cells.iter().take(stones).enumerate() {
+            board.play(Move::new(Color::from(i % 2 == 1), pos));
+        }
+        if board.winner().is_some() {
+            continue;
+        }
+        let to_play = board.to_play();
+        let winner = solve(&board);
+        let inferior = InferiorCells::new(&board);
+        let mut filled = board.snapshot();
+        for &(pos, color) in &inferior.fill {
+            filled.set(pos, Some(color));
+        }
+        filled.set_to_play(to_play);
+        let filled_winner = filled.winner().unwrap_or_else(|| solve(&filled));
+        assert_eq!(filled_winner, winner, "\n{}\n{}", board, filled);
+        // as in the search, all moves are considered if every one of them is inferior
+        let mut moves: Vec<Pos> = board.iter_empty()
+                                       .filter(|&pos| !inferior.is_inferior(pos))
+                                       .collect();
+        if moves.is_empty() {
+            moves = board.iter_empty().collect();
+        }
+        if winner == to_play {
+            let wins = moves.into_iter().any(|pos| {
+                let mut next = board.snapshot();
+                next.play(Move::new(to_play, pos));
+                next.winner() == Some(to_play) || solve(&next) == to_play
+            });
+            assert!(wins, "\n{}\n{:?}", board, inferior);
+        }
+    }
+}
@@ -1,18 +1,193 @@
-use super::Player;
-use super::board::{Board, Color, Move, Coord, Pos};
-use super::graph::{NodeRef, Node};
+use super::{Player, MoveStats};
+use super::board::{Board, BoardView, Color, Move, Coord, Pos, SwapKind};
+use super::graph::{NodeId, NodeRef, Tree};
+use super::timecontrol::TimeControl;
+use super::templates;
+use super::inferior::{self, InferiorCells};
+use super::patterns::PatternSet;
+use super::playout::PlayoutBoard;
+use super::solver::{self, Solution};
+use super::book::Book;
 use std::f32;
 use time;
-use rand::{self, thread_rng, Rng};
-use std::thread;
-use std::sync::atomic::{AtomicIsize, Ordering};
+use rand::{self, thread_rng, Rng, SeedableRng, XorShiftRng};
+use std::thread::{self, JoinHandle};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicIsize, AtomicUsize, AtomicBool, Ordering};
 use std::collections::HashSet;
 use std::hash::BuildHasherDefault;
 use fnv::FnvHasher;
+use std::str::FromStr;
+use std::fmt;
 
-const EXPLORATION: f32 = 0.1;
-const SEARCH_TIME: f32 = 1.0;
-const NUM_THREADS: usize = 4;
+/// How many previous search trees are kept, so that undoing and replaying a move can reuse them.
+const TREE_HISTORY: usize = 4;
+
+/// How many abandoned search trees are remembered by position, so that they can be picked up again
+/// if the position comes back, e.g. after setup moves or a change of the player to move.
+const TREE_CACHE_SIZE: usize = 4;
+
+/// How many rollouts a search thread performs between checks of whether the search can be stopped
+/// early.
+const CHECK_INTERVAL: usize = 64;
+
+/// The fraction of the tree's memory which may be in use when a search starts. Beyond it,
+/// subtrees with few visits are pruned to make room for the search.
+const PRUNE_TARGET: f32 = 0.5;
+
+/// How many of the most visited moves are listed in a search report.
+const REPORT_MOVES: usize = 8;
+
+/// How many moves of the principal variation are shown in a search report.
+const REPORT_PV_DEPTH: usize = 12;
+
+/// Tunable search parameters. These can be given on the command line or changed at runtime with
+/// the `param_mcts` HTP command.
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    /// UCT exploration constant.
+    pub exploration: f32,
+    /// RAVE equivalence parameter: the number of MC visits at which MC and RAVE are weighted
+    /// equally.
+    pub rave_k: f32,
+    /// Seconds to search for each move when there is no time limit.
+    pub search_time: f32,
+    /// Number of search threads.
+    pub num_threads: usize,
+    /// Number of rollouts to perform for each move. If nonzero, this replaces the time limit, so
+    /// that a seeded single threaded search is reproducible.
+    pub playouts: usize,
+    /// Whether to consider swapping when playing second.
+    pub allow_swap: bool,
+    /// Whether to keep searching between commands, while the opponent is thinking.
+    pub ponder: bool,
+    /// Whether to leave dead, captured and vulnerable cells out of the tree, and fill in dead and
+    /// captured cells before rollouts.
+    pub prune_inferior: bool,
+    /// Whether rollouts fill the whole board before finding the winner, instead of stopping as
+    /// soon as a player connects. This is faster, and RAVE then sees a move in every cell.
+    pub fill_board: bool,
+    /// Megabytes of memory for the search tree, and for the solver's table. Once the tree is full,
+    /// leaves are played out without being expanded. The system only provides the memory as the
+    /// tree grows into it.
+    pub max_memory: usize,
+    /// How many times a leaf must be visited before its children are added to the tree.
+    pub expand_visits: usize,
+    /// Limits on the solver: seconds, and positions searched, or zero for no limit. With a
+    /// playout budget, `genmove` ignores the time limit so that its moves stay reproducible, and
+    /// searches at most as many positions as playouts if there is no limit on positions.
+    pub solve_time: f32,
+    pub solve_nodes: usize,
+    /// How few empty cells there must be before `genmove` tries to solve the position, or zero to
+    /// never try.
+    pub solve_empty: usize,
+    /// The patterns which weight moves in rollouts, and the file they were loaded from, or
+    /// "builtin".
+    pub patterns: Arc<PatternSet>,
+    pub pattern_file: String,
+    /// The opening book consulted before searching, and the file it was loaded from, or "none".
+    pub book: Arc<Book>,
+    pub book_file: String,
+    /// How many times a book move must have been played before `genmove` trusts it.
+    pub book_min_visits: usize,
+}
+
+impl SearchConfig {
+    pub fn new() -> SearchConfig {
+        SearchConfig {
+            exploration: 0.1,
+            rave_k: 500.0,
+            search_time: 1.0,
+            num_threads: 4,
+            playouts: 0,
+            allow_swap: true,
+            ponder: false,
+            prune_inferior: true,
+            fill_board: true,
+            max_memory: 256,
+            expand_visits: 2,
+            solve_time: 10.0,
+            solve_nodes: 0,
+            solve_empty: 16,
+            patterns: Arc::new(PatternSet::new()),
+            pattern_file: "builtin".into(),
+            book: Arc::new(Book::new()),
+            book_file: "none".into(),
+            book_min_visits: 10,
+        }
+    }
+
+    /// List every parameter as a (name, value) pair.
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("exploration", self.exploration.to_string()),
+             ("rave_k", self.rave_k.to_string()),
+             ("search_time", self.search_time.to_string()),
+             ("num_threads", self.num_threads.to_string()),
+             ("playouts", self.playouts.to_string()),
+             ("allow_swap", (self.allow_swap as u8).to_string()),
+             ("ponder", (self.ponder as u8).to_string()),
+             ("prune_inferior", (self.prune_inferior as u8).to_string()),
+             ("fill_board", (self.fill_board as u8).to_string()),
+             ("max_memory", self.max_memory.to_string()),
+             ("expand_visits", self.expand_visits.to_string()),
+             ("solve_time", self.solve_time.to_string()),
+             ("solve_nodes", self.solve_nodes.to_string()),
+             ("solve_empty", self.solve_empty.to_string()),
+             ("patterns", self.pattern_file.clone()),
+             ("book", self.book_file.clone()),
+             ("book_min_visits", self.book_min_visits.to_string())]
+    }
+
+    /// Set a parameter by name, parsing the value from a string.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("invalid value for {}: {}", name, value))
+        }
+        match name {
+            "exploration" => self.exploration = try!(parse(name, value)),
+            "rave_k" => self.rave_k = try!(parse(name, value)),
+            "search_time" => self.search_time = try!(parse(name, value)),
+            "num_threads" => {
+                let n = try!(parse(name, value));
+                if n == 0 {
+                    return Err("num_threads must be at least 1".into());
+                }
+                self.num_threads = n;
+            }
+            "playouts" => self.playouts = try!(parse(name, value)),
+            "allow_swap" => self.allow_swap = try!(parse::<u8>(name, value)) != 0,
+            "ponder" => self.ponder = try!(parse::<u8>(name, value)) != 0,
+            "prune_inferior" => self.prune_inferior = try!(parse::<u8>(name, value)) != 0,
+            "fill_board" => self.fill_board = try!(parse::<u8>(name, value)) != 0,
+            "max_memory" => self.max_memory = try!(parse(name, value)),
+            "expand_visits" => self.expand_visits = try!(parse(name, value)),
+            "solve_time" => self.solve_time = try!(parse(name, value)),
+            "solve_nodes" => self.solve_nodes = try!(parse(name, value)),
+            "solve_empty" => self.solve_empty = try!(parse(name, value)),
+            "patterns" => {
+                let patterns = if value == "builtin" {
+                    PatternSet::new()
+                } else {
+                    try!(PatternSet::load(value))
+                };
+                self.patterns = Arc::new(patterns);
+                self.pattern_file = value.into();
+            }
+            "book" => {
+                let book = if value == "none" {
+                    Book::new()
+                } else {
+                    try!(Book::load(value))
+                };
+                self.book = Arc::new(book);
+                self.book_file = value.into();
+            }
+            "book_min_visits" => self.book_min_visits = try!(parse(name, value)),
+            _ => return Err(format!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug)]
 struct Stats {
@@ -52,6 +227,19 @@ impl Stats {
     pub fn reward(&self, reward: isize) {
         self.q.fetch_add(reward, Ordering::SeqCst);
     }
+
+    pub fn set(&self, n: isize, q: isize) {
+        self.n.store(n, Ordering::SeqCst);
+        self.q.store(q, Ordering::SeqCst);
+    }
+}
+
+/// The game theoretic value of a node, from the point of view of the player who made its move.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Proof {
+    Unknown,
+    Win,
+    Loss,
 }
 
 #[derive(Debug)]
@@ -59,6 +247,8 @@ struct MCTSNode {
     action: Move,
     mc: Stats,
     rave: Stats,
+    /// A `Proof`, stored as a number so that it can be updated by any thread.
+    proof: AtomicUsize,
 }
 
 impl MCTSNode {
@@ -67,21 +257,50 @@ impl MCTSNode {
             action: action,
             mc: Stats::new(),
             rave: Stats::new(),
+            proof: AtomicUsize::new(Proof::Unknown as usize),
+        }
+    }
+
+    pub fn proof(&self) -> Proof {
+        match self.proof.load(Ordering::SeqCst) {
+            1 => Proof::Win,
+            2 => Proof::Loss,
+            _ => Proof::Unknown,
+        }
+    }
+
+    pub fn prove(&self, proof: Proof) {
+        self.proof.store(proof as usize, Ordering::SeqCst);
+    }
+
+    /// The player who wins from this node, if it has been proven.
+    pub fn winner(&self) -> Option<Color> {
+        let mover = self.action.color();
+        match self.proof() {
+            Proof::Win => mover,
+            Proof::Loss => mover.map(|c| c.invert()),
+            Proof::Unknown => None,
         }
     }
 }
 
-impl Node<MCTSNode> {
-    pub fn value(&self) -> f32 {
+impl<'a> NodeRef<'a, MCTSNode> {
+    pub fn value(&self, config: &SearchConfig) -> f32 {
+        // proven moves are always played if they win, and never if they lose
+        match self.proof() {
+            Proof::Win => return f32::INFINITY,
+            Proof::Loss => return f32::NEG_INFINITY,
+            Proof::Unknown => (),
+        }
         let mc_n = self.mc.n();
         if mc_n == 0 {
-            if EXPLORATION == 0.0 {
+            if config.exploration == 0.0 {
                 0.0
             } else {
                 f32::INFINITY
             }
         } else {
-            let parent = self.parent().unwrap().upgrade();
+            let parent = self.parent().unwrap();
             let parent_n = parent.mc.n() as f32;
             let rave_q = self.rave.q() as f32;
             let mc_q = self.mc.q() as f32;
@@ -89,56 +308,200 @@ impl Node<MCTSNode> {
             let mc_n = mc_n as f32;
             let mc_mean = mc_q / mc_n;
             let rave_mean = rave_q / rave_n;
-            let k = 500.0;
-            let beta = k / (k + mc_n);
+            let k = config.rave_k;
+            // moves such as swaps never appear in rollouts, so they may have no RAVE stats
+            let beta = if rave_n == 0.0 { 0.0 } else { k / (k + mc_n) };
             let q = (1.0 - beta) * mc_mean + beta * rave_mean;
-            q * 2.0 + EXPLORATION * (2.0 * parent_n.ln() / mc_n).sqrt()
+            q * 2.0 + config.exploration * (2.0 * parent_n.ln() / mc_n).sqrt()
+        }
+    }
+}
+
+impl<'a> NodeRef<'a, MCTSNode> {
+    /// Prepare a node to become the root of the search tree. Its MC stats are recomputed from its
+    /// children, so that they only count rollouts which actually passed through it. Its RAVE
+    /// stats describe it as a move of its old parent, so they are cleared. It may have been proven
+    /// without being expanded, so its proof is also worked out again from its children.
+    fn make_root(&self) {
+        self.prove(Proof::Unknown);
+        self.update_proof();
+        let (mut n, mut q) = (0, 0);
+        for child in self.children() {
+            n += child.mc.n();
+            q += child.mc.n() - child.mc.q(); // children's wins are our losses
+        }
+        if n > 0 {
+            self.mc.set(n, q);
+        }
+        self.rave.set(0, 0);
+    }
+
+    /// Work out the value of a node from its children, which is proven if any child wins for the
+    /// player to move here, or every child loses for them. Returns true if the node was proven.
+    fn update_proof(&self) -> bool {
+        let children = self.children();
+        if children.clone().any(|child| child.proof() == Proof::Win) {
+            self.prove(Proof::Loss);
+            true
+        } else if children.len() > 0 &&
+                  children.clone().all(|child| child.proof() == Proof::Loss) {
+            self.prove(Proof::Win);
+            true
+        } else {
+            false
         }
     }
+
+    /// Check whether the most visited child leads the runner up by more visits than the search
+    /// can still perform.
+    fn is_decided(&self, remaining_visits: f32) -> bool {
+        let (mut best, mut second) = (0, 0);
+        let children = self.children();
+        let num_children = children.len();
+        for child in children {
+            let n = child.mc.n();
+            if n > best {
+                second = best;
+                best = n;
+            } else if n > second {
+                second = n;
+            }
+        }
+        num_children == 1 || (num_children > 1 && (best - second) as f32 > remaining_visits)
+    }
+}
+
+/// Limits on a search, shared between all of its threads.
+struct SearchBudget {
+    start_time: f64,
+    max_time: f32,
+    /// Maximum number of rollouts, or zero to search until the time runs out.
+    max_playouts: usize,
+    playouts: AtomicUsize,
+    stop: AtomicBool,
+}
+
+impl SearchBudget {
+    fn new(max_time: f32, max_playouts: usize) -> SearchBudget {
+        SearchBudget {
+            start_time: time::precise_time_s(),
+            max_time: max_time,
+            max_playouts: max_playouts,
+            playouts: AtomicUsize::new(0),
+            stop: AtomicBool::new(false),
+        }
+    }
+
+    /// Estimate how many more rollouts will be performed before the budget is exhausted.
+    fn remaining(&self) -> f32 {
+        let done = self.playouts.load(Ordering::SeqCst);
+        if self.max_playouts > 0 {
+            self.max_playouts.saturating_sub(done) as f32
+        } else {
+            let elapsed = (time::precise_time_s() - self.start_time) as f32;
+            done as f32 / elapsed * (self.max_time - elapsed)
+        }
+    }
+
+    /// Count a rollout, and return false if the budget has been exhausted.
+    fn consume(&self) -> bool {
+        if self.stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        if self.max_playouts > 0 {
+            self.playouts.fetch_add(1, Ordering::SeqCst) < self.max_playouts
+        } else {
+            self.playouts.fetch_add(1, Ordering::SeqCst);
+            time::precise_time_s() - self.start_time < self.max_time as f64
+        }
+    }
+}
+
+/// Seed a random number generator from a single number.
+fn seeded_rng(seed: u64) -> XorShiftRng {
+    // xorshift must not be seeded with all zeros
+    let (hi, lo) = ((seed >> 32) as u32, seed as u32);
+    XorShiftRng::from_seed([hi, lo, hi ^ 0x9e3779b9, (lo ^ 0x7f4a7c15) | 1])
 }
 
 struct SearchThread {
     board: Board,
-    tree: NodeRef<MCTSNode>,
+    tree: Arc<Tree<MCTSNode>>,
+    root: NodeId,
+    config: SearchConfig,
+    rng: XorShiftRng,
+    /// The position being played out, reused between rollouts.
+    playout: PlayoutBoard,
 }
 
 impl SearchThread {
-    fn new(board: Board, tree: NodeRef<MCTSNode>) -> SearchThread {
+    fn new(board: Board,
+           tree: Arc<Tree<MCTSNode>>,
+           root: NodeId,
+           config: SearchConfig,
+           rng: XorShiftRng)
+           -> SearchThread {
+        let playout = PlayoutBoard::new(&board, !config.fill_board);
         SearchThread {
+            playout: playout,
             board: board,
             tree: tree,
+            root: root,
+            config: config,
+            rng: rng,
         }
     }
 
-    /// Run Monte-Carlo search for the given number of seconds.
-    fn search(&mut self, max_time: f32) {
-        let start_time = time::precise_time_s();
+    /// Run Monte-Carlo search until the budget is exhausted, or the best move can no longer
+    /// change. Returns the number of rollouts performed.
+    fn search(&mut self, budget: &SearchBudget) -> usize {
         let mut num_rollouts = 0;
-        while time::precise_time_s() - start_time < max_time as f64 {
+        while budget.consume() {
             let (node, mut state) = self.select_node();
-            let outcome = self.roll_out(&mut state);
-            self.back_up(node, outcome, &state);
+            // there is no need to play out a position which is already decided
+            let winner = self.tree.get(node).winner();
+            let outcome = match winner {
+                Some(winner) => {
+                    self.playout.load(&state, !self.config.fill_board);
+                    winner
+                }
+                None => self.roll_out(&mut state),
+            };
+            self.back_up(node, outcome);
             num_rollouts += 1;
+
+            let root = self.tree.get(self.root);
+            if root.proof() != Proof::Unknown && !budget.stop.swap(true, Ordering::Relaxed) {
+                eprintln!("Solved the position after {:.2}s",
+                          time::precise_time_s() - budget.start_time);
+            }
+
+            if num_rollouts % CHECK_INTERVAL == 0 && root.is_decided(budget.remaining()) &&
+               !budget.stop.swap(true, Ordering::Relaxed) {
+                eprintln!("Stopping early after {:.2}s",
+                          time::precise_time_s() - budget.start_time);
+            }
         }
-        eprintln!("Num rollouts: {}", num_rollouts);
+        num_rollouts
     }
 
     /// Monte-Carlo selection process
-    fn select_node(&mut self) -> (NodeRef<MCTSNode>, Board) {
-        let mut node = self.tree.clone();
+    fn select_node(&mut self) -> (NodeId, Board) {
+        let mut node = self.tree.get(self.root);
         let mut state = self.board.clone();
 
         // virtual losses: visit node in selection process
         node.mc.visit(1);
 
-        while node.children().len() != 0 {
+        while node.has_children() {
             // find the child with the max value
-            let mut max_node = node.children()[0].clone();
-            let mut max_value = f32::NEG_INFINITY;
-            for child in node.children() {
-                let child_value = child.value();
+            let mut children = node.children();
+            let mut max_node = children.next().unwrap();
+            let mut max_value = max_node.value(&self.config);
+            for child in children {
+                let child_value = child.value(&self.config);
                 if child_value > max_value {
-                    max_node = child.clone();
+                    max_node = child;
                     max_value = child_value;
                 }
             }
@@ -149,92 +512,136 @@ impl SearchThread {
 
             // if it hasn't been visited yet, select it
             if node.mc.n() == 1 {
-                return (node, state);
+                self.check_proof(&node, &state);
+                return (node.id(), state);
+            }
+            // the outcome below a proven node is known, so there is nothing left to search there
+            if node.proof() != Proof::Unknown {
+                return (node.id(), state);
             }
         }
 
-        // if this is not a leaf node (no winner), expand the tree.
-        if state.winner().is_none() {
+        // if this is not a leaf node (no winner) and it has been visited often enough, expand the
+        // tree. When the tree is full, the leaf is played out without being expanded.
+        let visited = node.id() == self.root || node.mc.n() >= self.config.expand_visits as isize;
+        if visited && state.winner().is_none() && node.proof() == Proof::Unknown {
             self.expand(state.to_play(), &node, &state);
-            // choose a child randomly
-            let new_node = thread_rng().choose(node.children()).cloned().unwrap();
-            node = new_node;
+            let mut children = node.children();
+            if children.len() > 0 {
+                // choose a child randomly
+                let i = self.rng.gen_range(0, children.len());
+                node = children.nth(i).unwrap();
 
-            node.mc.visit(1); // virtual losses
-            state.play(node.action); // simulate action
+                node.mc.visit(1); // virtual losses
+                state.play(node.action); // simulate action
+                self.check_proof(&node, &state);
+            }
         }
 
-        (node, state)
+        (node.id(), state)
     }
 
-    /// Simulate a random game from a state and return the winner.
-    fn roll_out(&mut self, state: &mut Board) -> Color {
-        let mut empty_cells: Vec<Pos> = state.iter_empty().collect();
-        loop {
-            // check for a must play
-            let must_play = self.must_play(state);
-            let m = match must_play {
-                Move::Resign => break,
-                Move::None => {
-                    // no must play, pick random move
-                    let pos_idx = thread_rng().gen_range(0, empty_cells.len());
-                    let pos = empty_cells.remove(pos_idx);
-                    Move::new(state.to_play(), pos)
-                }
-                Move::Play { pos, color: _ } => {
-                    // must play, play it
-                    let idx = empty_cells.iter().position(|&x| x == pos).unwrap();
-                    empty_cells.remove(idx);
-                    must_play
-                }
-            };
-            if !state.play(m) {
-                panic!("roll out chose filled cell!");
+    /// Mark a node as proven if the player who moved there, or their opponent, has already won
+    /// the position it leads to through bridges and edge templates.
+    fn check_proof(&self, node: &NodeRef<MCTSNode>, state: &Board) {
+        if let Some(winner) = templates::virtual_winner(state) {
+            if node.action.color() == Some(winner) {
+                node.prove(Proof::Win);
+            } else {
+                node.prove(Proof::Loss);
             }
         }
-        state.winner().unwrap()
     }
 
-    fn must_play(&mut self, state: &Board) -> Move {
-        // game over, must play resign
-        if state.winner().is_some() {
-            return Move::Resign;
+    /// Simulate a game from a state and return the winner, leaving the final position in
+    /// `playout`. Moves near the opponent's last move are weighted by the rollout patterns, and
+    /// every other empty cell has weight 1.
+    fn roll_out(&mut self, state: &mut Board) -> Color {
+        if self.config.prune_inferior {
+            inferior::fill_in(state);
         }
+        let patterns = self.config.patterns.clone();
+        let playout = &mut self.playout;
+        playout.load(state, !self.config.fill_board);
+        let mut local: Vec<(Pos, f32)> = Vec::new();
+        while !playout.is_over() {
+            local.clear();
+            if let Move::Play { pos, .. } = playout.last_move() {
+                if !patterns.is_empty() {
+                    for n in patterns.neighborhood(pos) {
+                        if playout.on_board(n) && playout.is_empty(n) {
+                            local.push((n, patterns.weight(playout, n)));
+                        }
+                    }
+                }
+            }
 
-        // save bridge
-        let last_move = state.last_move();
-        if let Move::Play { pos, color: _ } = last_move {
-            let neighbor_patterns = &[(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)];
-            let num_pat = neighbor_patterns.len();
-            let start = thread_rng().gen_range(0, num_pat);
-            for i in start..(num_pat + start) {
-                let (end_a, end_b) = (pos + neighbor_patterns[i % num_pat].into(),
-                                      pos + neighbor_patterns[(i + 2) % num_pat].into());
-                let resp = pos + neighbor_patterns[(i + 1) % num_pat].into();
-                if state.get(end_a) == Some(state.to_play()) &&
-                   state.get(end_a) == state.get(end_b) &&
-                   state.get(resp).is_none() {
-                    return Move::new(state.to_play(), resp);
+            let local_weight: f32 = local.iter().map(|&(_, w)| w).sum();
+            let others = playout.num_empty() - local.len();
+            let mut r = self.rng.gen::<f32>() * (local_weight + others as f32);
+            let mut choice = None;
+            for &(pos, weight) in &local {
+                if r < weight {
+                    choice = Some(pos);
+                    break;
                 }
+                r -= weight;
             }
-        }
+            let pos = match choice {
+                Some(pos) => pos,
+                // only rounding can leave nothing chosen when every empty cell is local
+                None if others == 0 => local.last().unwrap().0,
+                // every other cell has the same weight, so draw until one is not local
+                None => {
+                    loop {
+                        let i = self.rng.gen_range(0, playout.num_empty());
+                        let pos = playout.empty_cell(i);
+                        if !local.iter().any(|&(p, _)| p == pos) {
+                            break pos;
+                        }
+                    }
+                }
+            };
 
-        // no mustplay
-        Move::None
+            if !playout.play(pos) {
+                panic!("roll out chose filled cell!");
+            }
+        }
+        playout.winner().unwrap()
     }
 
-    /// Adds all children (possible moves) to a node.
-    fn expand(&mut self, color: Color, node: &NodeRef<MCTSNode>, state: &Board) {
-        node.add_children(state.iter_empty()
-                               .map(|pos| NodeRef::new(MCTSNode::new(Move::new(color, pos))))
-                               .collect());
+    /// Adds all children (possible moves) to a node, except for inferior moves if they are pruned.
+    fn expand(&self, color: Color, node: &NodeRef<MCTSNode>, state: &Board) {
+        let mut moves: Vec<Pos> = if self.config.prune_inferior {
+            let inferior = InferiorCells::new(state);
+            state.iter_empty().filter(|&pos| !inferior.is_inferior(pos)).collect()
+        } else {
+            Vec::new()
+        };
+        // if every move is inferior, any of them is as good as the others
+        if moves.is_empty() {
+            moves = state.iter_empty().collect();
+        }
+        let mut children: Vec<_> = moves.into_iter()
+                                        .map(|pos| MCTSNode::new(Move::new(color, pos)))
+                                        .collect();
+        if self.config.allow_swap && state.can_swap(color) {
+            let swap = Move::Swap {
+                color: color,
+                kind: SwapKind::Pieces,
+            };
+            children.push(MCTSNode::new(swap));
+        }
+        self.tree.add_children(node.id(), children);
     }
 
     /// Propagate roll out results back up the tree
-    fn back_up(&mut self, mut node: NodeRef<MCTSNode>, outcome: Color, endgame: &Board) {
+    fn back_up(&self, node: NodeId, outcome: Color) {
+        let mut node = self.tree.get(node);
 
         // RAVE needs to keep track of all visited actions
         let hasher = BuildHasherDefault::<FnvHasher>::default();
+        let endgame = &self.playout;
         let mut actions = HashSet::with_capacity_and_hasher(endgame.dimensions().area(), hasher);
         actions.extend(endgame.iter_filled());
 
@@ -243,18 +650,21 @@ impl SearchThread {
         } else {
             0
         };
+        // whether the last node was proven, so that its parent may be too
+        let mut proven = node.proof() != Proof::Unknown;
         loop {
             node.mc.reward(reward);
             actions.insert(node.action);
 
-            let has_parent = node.parent().is_some();
-            if has_parent {
-                // move up the tree
-                let new_node = node.parent().unwrap().upgrade();
-                node = new_node;
-            } else {
+            // move up the tree, which may continue above the root into older searches
+            if node.id() == self.root {
                 break;
             }
+            node = node.parent().unwrap();
+
+            if proven {
+                proven = node.proof() != Proof::Unknown || node.update_proof();
+            }
 
             // RAVE
             for child in node.children() {
@@ -269,55 +679,358 @@ impl SearchThread {
     }
 }
 
+/// Statistics of a root move, as shown in a search report.
+#[derive(Clone, Debug)]
+struct ReportLine {
+    m: Move,
+    visits: isize,
+    mc_mean: f32,
+    rave_mean: f32,
+    uct: f32,
+    proof: Proof,
+}
+
+/// A summary of the last search, explaining why its move was chosen.
+#[derive(Clone, Debug)]
+struct SearchReport {
+    /// The most visited moves at the root, best first.
+    lines: Vec<ReportLine>,
+    pv: Vec<Move>,
+    rollouts: usize,
+    elapsed: f32,
+    /// Rollouts per second achieved by each thread.
+    rates: Vec<f32>,
+    tree_size: usize,
+    max_depth: usize,
+    /// Bytes of the search tree's memory in use, including older trees which are kept, and the
+    /// most it may use.
+    memory: usize,
+    max_memory: usize,
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(writeln!(f,
+                      "Searched {} rollouts in {:.2}s, tree size {}, max depth {}, memory {:.1} \
+                       of {:.1} MB",
+                      self.rollouts,
+                      self.elapsed,
+                      self.tree_size,
+                      self.max_depth,
+                      self.memory as f32 / (1 << 20) as f32,
+                      self.max_memory as f32 / (1 << 20) as f32));
+        try!(write!(f, "Rollouts/s per thread:"));
+        for rate in &self.rates {
+            try!(write!(f, " {:.0}", rate));
+        }
+        try!(writeln!(f,
+                      "\n{:<12} {:>8} {:>6} {:>6} {:>6} {:>5}",
+                      "move",
+                      "visits",
+                      "mc",
+                      "rave",
+                      "uct",
+                      "proof"));
+        for line in &self.lines {
+            let proof = match line.proof {
+                Proof::Win => "win",
+                Proof::Loss => "loss",
+                Proof::Unknown => "",
+            };
+            try!(writeln!(f,
+                          "{:<12} {:>8} {:>6.3} {:>6.3} {:>6.3} {:>5}",
+                          line.m.to_string(),
+                          line.visits,
+                          line.mc_mean,
+                          line.rave_mean,
+                          line.uct,
+                          proof));
+        }
+        try!(write!(f, "PV:"));
+        for m in &self.pv {
+            try!(write!(f, " {}", m));
+        }
+        Ok(())
+    }
+}
+
 pub struct MCTSPlayer {
     board: Board,
-    tree: NodeRef<MCTSNode>,
+    /// The nodes of the current search tree, and of any older trees which are kept.
+    tree: Arc<Tree<MCTSNode>>,
+    root: NodeId,
+    /// The root of the tree before each move was played, for the last few moves.
+    tree_history: Vec<Option<NodeId>>,
+    /// Recently abandoned trees, keyed by the position at their root.
+    tree_cache: Vec<((Vec<Move>, Color), NodeId)>,
+    setup: Vec<Move>,
     moves: Vec<Move>,
+    config: SearchConfig,
+    time: TimeControl,
+    rng: XorShiftRng,
+    /// The budget and threads of the search running in the background, if we are pondering.
+    pondering: Option<(Arc<SearchBudget>, Vec<JoinHandle<usize>>)>,
+    report: Option<SearchReport>,
 }
 
 impl MCTSPlayer {
-    pub fn new() -> MCTSPlayer {
+    pub fn new(config: SearchConfig) -> MCTSPlayer {
+        let tree = Tree::with_memory(config.max_memory << 20);
+        let root = tree.add_root(MCTSNode::new(Move::None)).unwrap();
         MCTSPlayer {
             board: Board::new((13, 13)),
-            tree: NodeRef::new(MCTSNode::new(Move::None)),
+            tree: Arc::new(tree),
+            root: root,
+            tree_history: Vec::new(),
+            tree_cache: Vec::new(),
+            setup: Vec::new(),
             moves: Vec::new(),
+            config: config,
+            time: TimeControl::new(),
+            rng: seeded_rng(thread_rng().next_u64()),
+            pondering: None,
+            report: None,
         }
     }
 
     /// Return the best move according to the current search tree.
     fn best_move(&mut self) -> Move {
-        // choose the node with the largest number of visits
-        let node = self.tree.clone();
-        let max = node.children().iter().map(|x| x.mc.n()).max().unwrap();
-        let max_nodes = node.children()
-                            .iter()
-                            .filter(|x| x.mc.n() == max);
+        let node = self.tree.get(self.root);
+        // a move which is known to win needs no further thought
+        if let Some(win) = node.children().find(|x| x.proof() == Proof::Win) {
+            return win.action;
+        }
+        // otherwise choose the node with the largest number of visits, avoiding moves which are
+        // known to lose unless there is nothing else
+        let mut candidates: Vec<_> = node.children()
+                                         .filter(|x| x.proof() != Proof::Loss)
+                                         .collect();
+        if candidates.is_empty() {
+            candidates = node.children().collect();
+        }
+        // the tree may be too small to hold even the moves from the root
+        if candidates.is_empty() {
+            let moves: Vec<Pos> = self.board.iter_empty().collect();
+            return Move::new(self.board.to_play(), *self.rng.choose(&moves).unwrap());
+        }
+        let max = candidates.iter().map(|x| x.mc.n()).max().unwrap();
+        let max_nodes = candidates.into_iter()
+                                  .filter(|x| x.mc.n() == max);
         // pick a random move that has the max visits
-        let best_node = rand::sample(&mut thread_rng(), max_nodes, 1)[0];
-        eprintln!("Win rate {}", best_node.mc.mean());
-        eprintln!("RAVE win rate {}", best_node.rave.mean());
+        let best_node = rand::sample(&mut self.rng, max_nodes, 1)[0];
         return best_node.action;
     }
 
-    fn search(&mut self, max_time: f32) {
-        // spawn search threads
+    /// Search the current position with the usual limits, without playing a move, and return the
+    /// statistics of each move considered.
+    pub fn analyze(&mut self) -> Vec<MoveStats> {
+        let search_time = self.config.search_time;
+        self.search(search_time);
+        self.move_stats()
+    }
+
+    /// Start search threads on the current tree, which run until the budget is exhausted.
+    fn spawn_search(&mut self, budget: &Arc<SearchBudget>) -> Vec<JoinHandle<usize>> {
+        self.collect_garbage();
         let mut threads = Vec::new();
-        for _ in 0..NUM_THREADS {
-            let board = self.board.clone();
+        for _ in 0..self.config.num_threads {
+            let board = self.board.snapshot();
             let tree = self.tree.clone();
+            let root = self.root;
+            let config = self.config.clone();
+            let rng = seeded_rng(self.rng.next_u64());
+            let budget = budget.clone();
             threads.push(thread::spawn(move || {
-                let mut st = SearchThread::new(board, tree);
-                st.search(max_time);
+                let mut st = SearchThread::new(board, tree, root, config, rng);
+                st.search(&budget)
             }));
         }
-        for t in threads {
-            t.join().unwrap();
+        threads
+    }
+
+    fn search(&mut self, max_time: f32) {
+        let budget = Arc::new(SearchBudget::new(max_time, self.config.playouts));
+        let counts: Vec<_> = self.spawn_search(&budget)
+                                 .into_iter()
+                                 .map(|t| t.join().unwrap())
+                                 .collect();
+        let elapsed = (time::precise_time_s() - budget.start_time) as f32;
+        let report = self.report(elapsed, &counts);
+        eprintln!("{}", report);
+        self.report = Some(report);
+    }
+
+    /// Summarize the search tree after a search which took `elapsed` seconds, in which each
+    /// thread performed the given number of rollouts.
+    fn report(&self, elapsed: f32, counts: &[usize]) -> SearchReport {
+        let root = self.tree.get(self.root);
+        let mut lines: Vec<_> = root.children()
+                                    .map(|child| {
+                                        ReportLine {
+                                            m: child.action,
+                                            visits: child.mc.n(),
+                                            mc_mean: child.mc.mean(),
+                                            rave_mean: child.rave.mean(),
+                                            uct: child.value(&self.config),
+                                            proof: child.proof(),
+                                        }
+                                    })
+                                    .collect();
+        lines.sort_by(|a, b| b.visits.cmp(&a.visits));
+        lines.truncate(REPORT_MOVES);
+        let mut pv = self.principal_variation();
+        pv.truncate(REPORT_PV_DEPTH);
+        SearchReport {
+            lines: lines,
+            pv: pv,
+            rollouts: counts.iter().sum(),
+            elapsed: elapsed,
+            rates: counts.iter().map(|&n| n as f32 / elapsed).collect(),
+            tree_size: root.tree_size(),
+            max_depth: root.tree_depth(),
+            memory: self.tree.len() * Tree::<MCTSNode>::node_size(),
+            max_memory: self.tree.capacity() * Tree::<MCTSNode>::node_size(),
+        }
+    }
+
+    /// Remember the current tree, which is searching the position with key `key`.
+    fn cache_tree(&mut self, key: (Vec<Move>, Color)) {
+        if !self.tree.get(self.root).has_children() {
+            return;
+        }
+        self.tree_cache.retain(|&(ref k, _)| *k != key);
+        if self.tree_cache.len() >= TREE_CACHE_SIZE {
+            self.tree_cache.remove(0);
+        }
+        self.tree_cache.push((key, self.root));
+    }
+
+    /// Find a tree for the current position, either among the children of the current root or in
+    /// the cache, and make it the root. If there is none, start a new tree.
+    fn find_tree(&mut self, m: Move) {
+        let child = self.tree
+                        .get(self.root)
+                        .children()
+                        .find(|x| x.action == m)
+                        .map(|x| x.id());
+        let key = self.board.position_key();
+        let cached = self.tree_cache
+                         .iter()
+                         .position(|&(ref k, _)| *k == key)
+                         .map(|i| self.tree_cache.remove(i).1);
+        if let Some(new_root) = child.or(cached) {
+            self.root = new_root;
+        } else {
+            eprintln!("No search tree to reuse");
+            self.clear_tree();
+            return;
         }
-        eprintln!("Tree size: {}", self.tree.tree_size());
+        let root = self.tree.get(self.root);
+        root.make_root();
+        eprintln!("Reusing search tree with {} visits", root.mc.n());
     }
 
+    /// Start a new tree for the current position.
     fn clear_tree(&mut self) {
-        self.tree = NodeRef::new(MCTSNode::new(Move::None));
+        let new_root = self.tree.add_root(MCTSNode::new(Move::None));
+        self.root = match new_root {
+            Some(root) => root,
+            None => {
+                // there is no room left, so forget the older trees as well
+                for old_root in &mut self.tree_history {
+                    *old_root = None;
+                }
+                self.tree_cache.clear();
+                let tree = Arc::get_mut(&mut self.tree).expect("search threads are still running");
+                tree.clear();
+                tree.add_root(MCTSNode::new(Move::None)).unwrap()
+            }
+        };
+    }
+
+    /// Free the nodes which can't be reached from the current tree, or from the older trees kept
+    /// for undo and for positions which may come back. If the remaining nodes still take up too
+    /// much of the tree's memory, subtrees with few visits are pruned as well.
+    fn collect_garbage(&mut self) {
+        let mut roots = vec![self.root];
+        roots.extend(self.tree_history.iter().filter_map(|&old_root| old_root));
+        roots.extend(self.tree_cache.iter().map(|&(_, root)| root));
+        {
+            let tree = Arc::get_mut(&mut self.tree).expect("search threads are still running");
+            tree.collect(&mut roots);
+            let target = (tree.capacity() as f32 * PRUNE_TARGET) as usize;
+            if tree.len() > target {
+                let before = tree.len();
+                let threshold = prune(tree, &roots, roots[0], target);
+                tree.collect(&mut roots);
+                eprintln!("Pruned {} nodes with fewer than {} visits",
+                          before - tree.len(),
+                          threshold);
+            }
+        }
+
+        let mut moved = roots.into_iter();
+        self.root = moved.next().unwrap();
+        for old_root in self.tree_history.iter_mut().filter(|old_root| old_root.is_some()) {
+            *old_root = moved.next();
+        }
+        for &mut (_, ref mut root) in &mut self.tree_cache {
+            *root = moved.next().unwrap();
+        }
+
+        // the root must have room for its children, or the search can't choose a move
+        let needed = self.board.dimensions().area() + 1;
+        let has_old_trees = self.tree_history.iter().any(|old_root| old_root.is_some()) ||
+                            !self.tree_cache.is_empty();
+        if !self.tree.get(self.root).has_children() &&
+           self.tree.capacity() - self.tree.len() < needed && has_old_trees {
+            for old_root in &mut self.tree_history {
+                *old_root = None;
+            }
+            self.tree_cache.clear();
+            self.collect_garbage();
+        }
+    }
+}
+
+/// Remove the children of nodes with few visits, other than `keep`, until the nodes reachable from
+/// `roots` fit in `target`, or only `keep` and its children are left. Returns the number of
+/// visits below which nodes were pruned. The pruned nodes are freed by the next `collect`.
+fn prune(tree: &mut Tree<MCTSNode>, roots: &[NodeId], keep: NodeId, target: usize) -> isize {
+    // find the nodes which would be left with a threshold, and the nodes to cut below
+    let reachable = |tree: &Tree<MCTSNode>, threshold: isize| {
+        let mut seen = vec![false; tree.len()];
+        let mut stack = roots.to_vec();
+        let (mut size, mut cut, mut max_visits) = (0, Vec::new(), 0);
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            size += 1;
+            let node = tree.get(id);
+            if id != keep && node.mc.n() < threshold {
+                if node.has_children() {
+                    cut.push(id);
+                }
+                continue;
+            }
+            max_visits = max_visits.max(node.mc.n());
+            stack.extend(node.children().map(|child| child.id()));
+        }
+        (size, cut, max_visits)
+    };
+
+    let mut threshold = 2;
+    loop {
+        let (size, cut, max_visits) = reachable(tree, threshold);
+        if size <= target || threshold > max_visits {
+            for id in cut {
+                tree.remove_children(id);
+            }
+            return threshold;
+        }
+        threshold *= 2;
     }
 }
 
@@ -328,38 +1041,90 @@ impl Player for MCTSPlayer {
         }
 
         if color != self.board.to_play() {
+            // the tree is for the other player, but we may want it back later
+            let key = self.board.position_key();
+            self.cache_tree(key);
             self.board.set_to_play(color);
-            self.clear_tree();
+            self.find_tree(Move::None);
+        }
+
+        // book moves take next to no time, but they still count on the clock, e.g. as byoyomi
+        // stones
+        let start_time = time::precise_time_s();
+        if let Some(m) = self.config.book.best_move(&self.board, self.config.book_min_visits) {
+            if self.play_move(m) {
+                eprintln!("Played {} from the book", m);
+                self.time.charge(color, (time::precise_time_s() - start_time) as f32);
+                return m;
+            }
+        }
+
+        let search_time = self.time
+                              .allocate(color,
+                                        self.board.iter_empty().count(),
+                                        self.board.dimensions().area())
+                              .unwrap_or(self.config.search_time);
+
+        // in the endgame, try to prove a win first, with at most half of the time
+        if self.config.solve_empty > 0 &&
+           self.board.iter_empty().count() <= self.config.solve_empty {
+            let (max_time, max_nodes) = if self.config.playouts > 0 {
+                let nodes = self.config.solve_nodes;
+                (f32::INFINITY, if nodes > 0 { nodes } else { self.config.playouts })
+            } else {
+                (self.config.solve_time.min(search_time / 2.0), self.config.solve_nodes)
+            };
+            let solution = solver::solve(&self.board,
+                                         max_time,
+                                         max_nodes,
+                                         self.config.max_memory << 20);
+            if let Some(Solution { best_move: Some(m), .. }) = solution {
+                eprintln!("Solved the position: {} wins", color);
+                self.time.charge(color, (time::precise_time_s() - start_time) as f32);
+                self.play_move(m);
+                return m;
+            }
         }
-        self.search(SEARCH_TIME);
+        let elapsed = (time::precise_time_s() - start_time) as f32;
+        self.search(search_time - elapsed);
         let m = self.best_move();
+        self.time.charge(color, (time::precise_time_s() - start_time) as f32);
         self.play_move(m);
         m
     }
 
     /// Force a move.
     fn play_move(&mut self, m: Move) -> bool {
-        let node = self.tree
-                       .children()
-                       .iter()
-                       .find(|x| x.action == m)
-                       .cloned();
-        if let Some(mut new_root) = node {
-            // if the move is in the tree, make it the new root
-            new_root.orphan();
-            self.tree = new_root;
-        } else {
-            // otherwise, make a new root
-            self.clear_tree();
+        let key = self.board.position_key();
+        if !self.board.make_move(m) {
+            return false;
         }
         self.moves.push(m);
-        self.board.play(m)
+
+        // keep the old tree around in case this move is undone
+        self.tree_history.push(Some(self.root));
+        if self.tree_history.len() > TREE_HISTORY {
+            let old = self.tree_history.len() - TREE_HISTORY - 1;
+            self.tree_history[old] = None;
+        }
+
+        // a move by the player who isn't to move, e.g. a setup move, can't be in the tree
+        if m.color() != Some(key.1) {
+            self.cache_tree(key);
+        }
+        self.find_tree(m);
+        true
     }
 
     fn undo(&mut self) {
-        if let Some(Move::Play { pos, color: _}) = self.moves.pop() {
-            self.board.clear_cell(pos);
-            self.clear_tree();
+        if self.board.undo().is_none() {
+            return;
+        }
+        self.moves.pop();
+        match self.tree_history.pop() {
+            // the current tree is still a child of the old root, so replaying the move reuses it
+            Some(Some(old_root)) => self.root = old_root,
+            _ => self.clear_tree(),
         }
     }
 
@@ -367,6 +1132,25 @@ impl Player for MCTSPlayer {
         &self.board
     }
 
+    fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    fn set_up(&mut self, stones: &[Move]) -> bool {
+        if !self.board.set_up(stones) {
+            return false;
+        }
+        self.setup.extend_from_slice(stones);
+        // the trees are for positions without the stones
+        self.tree_history.clear();
+        self.clear_tree();
+        true
+    }
+
+    fn setup(&self) -> &[Move] {
+        &self.setup
+    }
+
     fn name(&self) -> String {
         "coronene mcts".into()
     }
@@ -377,7 +1161,167 @@ impl Player for MCTSPlayer {
 
     fn set_board_size(&mut self, cols: Coord, rows: Coord) {
         self.board = Board::new((cols, rows));
+        self.tree_history.clear();
+        self.tree_cache.clear();
+        Arc::get_mut(&mut self.tree).expect("search threads are still running").clear();
         self.clear_tree();
+        self.setup.clear();
         self.moves.clear();
+        self.report = None;
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        self.config.params().into_iter().map(|(name, value)| (name.into(), value)).collect()
+    }
+
+    fn set_param(&mut self, name: &str, value: &str) -> Result<(), String> {
+        try!(self.config.set(name, value));
+        if name == "max_memory" {
+            self.tree = Arc::new(Tree::with_memory(self.config.max_memory << 20));
+            self.tree_history.clear();
+            self.tree_cache.clear();
+            self.clear_tree();
+        }
+        Ok(())
+    }
+
+    fn time_control(&mut self) -> &mut TimeControl {
+        &mut self.time
     }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = seeded_rng(seed);
+    }
+
+    fn start_pondering(&mut self) {
+        if !self.config.ponder || self.pondering.is_some() || self.board.winner().is_some() {
+            return;
+        }
+        // search until told to stop, regardless of the usual limits
+        let budget = Arc::new(SearchBudget::new(f32::INFINITY, 0));
+        let threads = self.spawn_search(&budget);
+        self.pondering = Some((budget, threads));
+    }
+
+    fn stop_pondering(&mut self) {
+        if let Some((budget, threads)) = self.pondering.take() {
+            budget.stop.store(true, Ordering::Relaxed);
+            let rollouts: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+            eprintln!("Pondered {} rollouts", rollouts);
+        }
+    }
+
+    fn move_stats(&self) -> Vec<MoveStats> {
+        self.tree
+            .get(self.root)
+            .children()
+            .map(|child| {
+                MoveStats {
+                    m: child.action,
+                    visits: child.mc.n() as usize,
+                    win_rate: child.mc.mean(),
+                    rave_visits: child.rave.n() as usize,
+                    rave_win_rate: child.rave.mean(),
+                    proven: match child.proof() {
+                        Proof::Win => Some(true),
+                        Proof::Loss => Some(false),
+                        Proof::Unknown => None,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn principal_variation(&self) -> Vec<Move> {
+        // follow a winning child if there is one, and otherwise the most visited child for as long
+        // as it has been visited
+        let mut pv = Vec::new();
+        let mut node = self.tree.get(self.root);
+        loop {
+            let best = node.children()
+                           .find(|x| x.proof() == Proof::Win)
+                           .or_else(|| node.children().max_by_key(|x| x.mc.n()));
+            match best {
+                Some(child) if child.mc.n() > 0 => {
+                    pv.push(child.action);
+                    node = child;
+                }
+                _ => break,
+            }
+        }
+        pv
+    }
+
+    fn solve(&mut self, color: Color) -> Option<Solution> {
+        let mut board = self.board.snapshot();
+        board.set_to_play(color);
+        solver::solve(&board,
+                      self.config.solve_time,
+                      self.config.solve_nodes,
+                      self.config.max_memory << 20)
+    }
+
+    fn book(&mut self) -> &mut Book {
+        Arc::make_mut(&mut self.config.book)
+    }
+
+    fn search_info(&self) -> String {
+        match self.report {
+            Some(ref report) => report.to_string(),
+            None => "no search has been performed".into(),
+        }
+    }
+}
+
+#[test]
+fn test_solver() {
+    let mut config = SearchConfig::new();
+    config.num_threads = 1;
+    config.playouts = 2000;
+    config.allow_swap = false;
+    let mut player = MCTSPlayer::new(config);
+    player.set_seed(1);
+
+    // on an empty 3x3 board the first player has winning moves, and once one is proven it is
+    // played regardless of visits
+    player.set_board_size(3, 3);
+    player.search(f32::INFINITY);
+    let m = player.best_move();
+    let root = player.tree.get(player.root);
+    assert!(root.children().any(|x| x.action == m && x.proof() == Proof::Win));
+    assert!(player.report.as_ref().unwrap().rollouts < 2000);
+
+    // after the centre, every reply is lost through bridges, so the whole position is proven
+    player.set_board_size(3, 3);
+    player.play_move(Move::new(Color::Black, (1, 1)));
+    player.search(f32::INFINITY);
+    let root = player.tree.get(player.root);
+    assert!(root.children().all(|x| x.proof() == Proof::Loss));
+    assert_eq!(root.proof(), Proof::Win);
+    assert!(player.report.as_ref().unwrap().rollouts < 2000);
+}
+
+#[test]
+fn test_reproducible() {
+    // with a playout budget and a seed, whole games are the same every time, even with the
+    // solver taking over in the endgame
+    let play_game = || {
+        let mut config = SearchConfig::new();
+        config.num_threads = 1;
+        config.playouts = 300;
+        config.max_memory = 16;
+        config.solve_empty = 25;
+        let mut player = MCTSPlayer::new(config);
+        player.set_seed(7);
+        player.set_board_size(5, 5);
+        let mut moves = Vec::new();
+        while player.board().winner().is_none() {
+            let color = player.board().to_play();
+            moves.push(player.generate_move(color));
+        }
+        moves
+    };
+    let moves = play_game();
+    assert!(moves.len() > 1);
+    assert_eq!(play_game(), moves);
 }
@@ -1 +1 @@
-pub mod atomic_vec;
+pub mod bitset;
@@ -12,15 +12,69 @@ pub mod htp;
 //pub mod randomplayer;
 pub mod mctsplayer;
 pub mod misc;
+pub mod timecontrol;
+pub mod sgf;
+pub mod vc;
+pub mod templates;
+pub mod inferior;
+pub mod patterns;
+pub mod playout;
+pub mod solver;
+pub mod book;
+pub mod bookbuilder;
 
 use self::board::{Board, Coord, Color, Move};
+use self::timecontrol::TimeControl;
+use self::solver::Solution;
+use self::book::Book;
+
+/// Search statistics for one of the moves considered in the current position.
+#[derive(Clone, Debug)]
+pub struct MoveStats {
+    pub m: Move,
+    pub visits: usize,
+    /// Fraction of visits won by the player making the move.
+    pub win_rate: f32,
+    pub rave_visits: usize,
+    pub rave_win_rate: f32,
+    /// Whether the move is known to win or to lose, if it has been proven either way.
+    pub proven: Option<bool>,
+}
 
 pub trait Player {
     fn generate_move(&mut self, color: Color) -> Move;
     fn play_move(&mut self, m: Move) -> bool;
     fn undo(&mut self);
     fn board(&self) -> &Board;
+    /// Every move played so far, in order.
+    fn moves(&self) -> &[Move];
+    /// Place setup stones on the board, which are not moves. Returns false if one of them can't
+    /// be placed.
+    fn set_up(&mut self, stones: &[Move]) -> bool;
+    /// The setup stones placed since the board was last cleared.
+    fn setup(&self) -> &[Move];
     fn name(&self) -> String;
     fn version(&self) -> String;
     fn set_board_size(&mut self, cols: Coord, rows: Coord);
+    /// List the player's tunable parameters as (name, value) pairs.
+    fn params(&self) -> Vec<(String, String)>;
+    fn set_param(&mut self, name: &str, value: &str) -> Result<(), String>;
+    fn time_control(&mut self) -> &mut TimeControl;
+    /// Seed the player's random number generator, making its moves reproducible.
+    fn set_seed(&mut self, seed: u64);
+    /// Start thinking in the background while waiting for the next command, if enabled.
+    fn start_pondering(&mut self);
+    /// Stop thinking in the background. This must be called before the player is used again.
+    fn stop_pondering(&mut self);
+    /// Statistics for each move considered by the last search in the current position.
+    fn move_stats(&self) -> Vec<MoveStats>;
+    /// The line of play the player currently expects, starting from the current position.
+    fn principal_variation(&self) -> Vec<Move>;
+    /// Try to prove who wins the current position with `color` to move. Returns `None` if the
+    /// solver's limits are reached first.
+    fn solve(&mut self, color: Color) -> Option<Solution>;
+    /// The opening book, which may be edited.
+    fn book(&mut self) -> &mut Book;
+    /// A human readable summary of the last search.
+    fn search_info(&self) -> String;
 }
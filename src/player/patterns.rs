@@ -0,0 +1,236 @@
+//! Local patterns which weight the moves made in rollouts. The text format is described in
+//! `patterns/default.txt`, which is built in and used unless another file is loaded.
+
+use std::fs::File;
+use std::io::Read;
+use super::board::{BoardView, Move, Pos};
+
+/// The patterns used when no file is given.
+pub const DEFAULT_PATTERNS: &'static str = include_str!("../../patterns/default.txt");
+
+/// The neighbors of a cell, clockwise starting from the left.
+const NEIGHBORS: [(i16, i16); 6] = [(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)];
+
+/// The state of a cell, as two bits of a neighborhood code.
+const EMPTY: u64 = 0;
+const OWN: u64 = 1;
+const OPPONENT: u64 = 2;
+const OFF_BOARD: u64 = 3;
+
+/// How many cells a pattern covers.
+const SIZE: usize = 18;
+
+/// The bits of a neighborhood code which hold the inner ring.
+const RING1: u64 = (1 << 12) - 1;
+
+/// A pattern in one of its orientations, as a mask and value to compare neighborhood codes with.
+#[derive(Clone, Debug, PartialEq)]
+struct Shape {
+    mask: u64,
+    value: u64,
+    weight: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct PatternSet {
+    /// Every orientation of every pattern, grouped by which cell of the neighborhood must be the
+    /// last move, with a last group for those which don't need one.
+    shapes: Vec<Vec<Shape>>,
+    /// The cells at distance one and two from a cell, in the order used by the text format.
+    offsets: Vec<Pos>,
+}
+
+impl PatternSet {
+    /// The built in patterns.
+    pub fn new() -> PatternSet {
+        PatternSet::parse(DEFAULT_PATTERNS).expect("invalid built in patterns")
+    }
+
+    pub fn load(path: &str) -> Result<PatternSet, String> {
+        let mut text = String::new();
+        try!(File::open(path)
+                 .and_then(|mut f| f.read_to_string(&mut text))
+                 .map_err(|e| format!("cannot read {}: {}", path, e)));
+        PatternSet::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<PatternSet, String> {
+        let offsets = neighborhood();
+        let mut shapes = vec![Vec::new(); SIZE + 1];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let err = |msg: &str| format!("line {}: {}", i + 1, msg);
+            // the name is only there for people reading the file
+            let (weight, cells) = match words[..] {
+                [_, weight, ring1] => (weight, ring1.to_string()),
+                [_, weight, ring1, ring2] => (weight, format!("{}{}", ring1, ring2)),
+                _ => return Err(err("expected name, weight and rings")),
+            };
+            let weight = match weight.parse::<f32>() {
+                Ok(w) if w >= 0.0 => w,
+                _ => return Err(err("invalid weight")),
+            };
+            if cells.len() != 6 && cells.len() != 18 {
+                return Err(err("rings must have 6 and 12 cells"));
+            }
+
+            // describe the pattern by its offsets, then find its code in every orientation
+            let mut cells: Vec<(Pos, char)> = offsets.iter().cloned().zip(cells.chars()).collect();
+            for _ in 0..2 {
+                for _ in 0..6 {
+                    let (last_move, shape) = try!(shape(&offsets, &cells, weight).map_err(|c| {
+                        err(&format!("invalid cell: {}", c))
+                    }));
+                    let group = &mut shapes[last_move.unwrap_or(SIZE)];
+                    if !group.contains(&shape) {
+                        group.push(shape);
+                    }
+                    for cell in &mut cells {
+                        cell.0 = rotate(cell.0);
+                    }
+                }
+                for cell in &mut cells {
+                    cell.0 = Pos::new(cell.0.y, cell.0.x);
+                }
+            }
+        }
+        Ok(PatternSet {
+            shapes: shapes,
+            offsets: offsets,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.iter().all(|group| group.is_empty())
+    }
+
+    /// The cells around a position which patterns can see.
+    pub fn neighborhood<'a>(&'a self, pos: Pos) -> impl Iterator<Item = Pos> + 'a {
+        self.offsets.iter().map(move |&offset| pos + offset)
+    }
+
+    /// The weight of playing in an empty cell for the player to move: the largest weight of the
+    /// patterns which match it, or 1 if none do.
+    pub fn weight<B: BoardView>(&self, board: &B, pos: Pos) -> f32 {
+        let to_play = board.to_play();
+        let last_idx = match board.last_move() {
+            Move::Play { color, pos: last } if color != to_play => {
+                self.offsets.iter().position(|&offset| pos + offset == last)
+            }
+            _ => None,
+        };
+        let at_last = last_idx.map_or(&[][..], |i| &self.shapes[i][..]);
+        let shapes = || at_last.iter().chain(&self.shapes[SIZE]);
+
+        // the inner ring rules out most shapes, so only look further if one of them is left
+        let mut code = self.code(board, pos, 0, 6);
+        if !shapes().any(|shape| code & shape.mask & RING1 == shape.value & RING1) {
+            return 1.0;
+        }
+        code |= self.code(board, pos, 6, SIZE);
+        shapes().filter(|shape| code & shape.mask == shape.value)
+                .map(|shape| shape.weight)
+                .fold(None, |best: Option<f32>, w| Some(best.map_or(w, |b| b.max(w))))
+                .unwrap_or(1.0)
+    }
+
+    /// The neighborhood code of the cells `start..end` around a position.
+    fn code<B: BoardView>(&self, board: &B, pos: Pos, start: usize, end: usize) -> u64 {
+        let to_play = board.to_play();
+        let mut code = 0;
+        for i in start..end {
+            let n = pos + self.offsets[i];
+            let state = match board.get(n) {
+                Some(c) if c == to_play => OWN,
+                Some(_) => OPPONENT,
+                None if board.on_board(n) => EMPTY,
+                None => OFF_BOARD,
+            };
+            code |= state << (2 * i);
+        }
+        code
+    }
+}
+
+/// The cells at distance one and then two, clockwise starting from the left.
+fn neighborhood() -> Vec<Pos> {
+    let ring1: Vec<Pos> = NEIGHBORS.iter().map(|&n| n.into()).collect();
+    let mut offsets = ring1.clone();
+    for i in 0..6 {
+        let (a, b) = (ring1[i], ring1[(i + 1) % 6]);
+        offsets.push(a + a);
+        offsets.push(a + b);
+    }
+    offsets
+}
+
+/// Rotate an offset by one step clockwise.
+fn rotate(pos: Pos) -> Pos {
+    Pos::new(-pos.y, pos.x + pos.y)
+}
+
+/// Work out the code of a pattern given as the character for each offset, and where its last move
+/// is. Returns the first character which is not understood as an error.
+fn shape(offsets: &[Pos],
+         cells: &[(Pos, char)],
+         weight: f32)
+         -> Result<(Option<usize>, Shape), char> {
+    let mut last_move = None;
+    let mut shape = Shape {
+        mask: 0,
+        value: 0,
+        weight: weight,
+    };
+    for &(offset, c) in cells {
+        let i = offsets.iter().position(|&o| o == offset).unwrap();
+        let state = match c {
+            '.' => EMPTY,
+            'O' => OWN,
+            'X' => OPPONENT,
+            'L' => {
+                last_move = Some(i);
+                OPPONENT
+            }
+            '?' => continue,
+            c => return Err(c),
+        };
+        shape.mask |= 3 << (2 * i);
+        shape.value |= state << (2 * i);
+    }
+    Ok((last_move, shape))
+}
+
+#[test]
+fn test_patterns() {
+    use super::board::{Board, Color};
+
+    let patterns = PatternSet::new();
+    assert!(!patterns.is_empty());
+
+    // the opponent intrudes into a bridge: saving it is weighted, whichever way the bridge lies
+    for &(a, b, intrusion, save) in &[((1, 1), (2, 2), (2, 1), (1, 2)),
+                                      ((3, 1), (2, 3), (2, 2), (3, 2)),
+                                      ((1, 2), (3, 1), (2, 1), (2, 2))] {
+        let mut board = Board::new((5, 5));
+        board.play(Move::new(Color::Black, a));
+        board.play(Move::new(Color::White, (4, 4)));
+        board.play(Move::new(Color::Black, b));
+        board.play(Move::new(Color::White, intrusion));
+        assert!(patterns.weight(&board, save.into()) > 1.0);
+        assert_eq!(patterns.weight(&board, Pos::new(0, 4)), 1.0);
+    }
+
+    // edges count as stones
+    let mut board = Board::new((5, 5));
+    board.play(Move::new(Color::Black, (2, 1)));
+    board.play(Move::new(Color::White, (2, 0)));
+    assert!(patterns.weight(&board, Pos::new(3, 0)) > 1.0);
+
+    for bad in &["a 1 OLO??", "a x OLO???", "a 1 OLO??Z", "a 1 OLO??? ...", "a 1"] {
+        assert!(PatternSet::parse(bad).is_err());
+    }
+}
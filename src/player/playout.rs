@@ -0,0 +1,311 @@
+//! A board specialized for rollouts. The cells are kept in a flat array with a border around the
+//! board, so that neighbors and pattern cells can be read without bounds checks, and the empty
+//! cells are kept in a list which a move is removed from in constant time.
+//!
+//! By default the winner is not tracked while moves are played. Hex has no draws, so a rollout can
+//! fill the whole board and find the winner once at the end, which has the same outcome as
+//! stopping when a player first connects their edges.
+
+use super::board::{Board, BoardView, Color, Move, Pos};
+
+/// How many cells of border surround the board: enough for the patterns to look two cells away.
+const BORDER: usize = 2;
+
+/// The neighbors of a cell.
+const NEIGHBORS: [(isize, isize); 6] = [(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)];
+
+#[derive(Clone, Debug)]
+pub struct PlayoutBoard {
+    dims: Pos,
+    /// The width of a row of `cells`, including the border.
+    width: usize,
+    /// The color of each cell, or of the edge for border cells. Empty cells and the corners of the
+    /// border are `None`.
+    cells: Vec<Option<Color>>,
+    /// The offsets of the neighbors of a cell in `cells`.
+    neighbors: [isize; 6],
+    empty: Vec<usize>,
+    /// Where each empty cell is in `empty`.
+    empty_idx: Vec<usize>,
+    to_play: Color,
+    last_move: Move,
+    /// The union-find parent of each cell if the winner is tracked as moves are played, or empty
+    /// if it is only found when asked for.
+    parents: Vec<usize>,
+    winner: Option<Color>,
+}
+
+impl PlayoutBoard {
+    pub fn new(board: &Board, track_winner: bool) -> PlayoutBoard {
+        let mut playout = PlayoutBoard {
+            dims: Pos::new(0, 0),
+            width: 0,
+            cells: Vec::new(),
+            neighbors: [0; 6],
+            empty: Vec::new(),
+            empty_idx: Vec::new(),
+            to_play: Color::Black,
+            last_move: Move::None,
+            parents: Vec::new(),
+            winner: None,
+        };
+        playout.load(board, track_winner);
+        playout
+    }
+
+    /// Copy a position, reusing the memory of the previous one. If `track_winner` is set, the
+    /// winner is kept up to date as moves are played, so that a rollout can stop as soon as there
+    /// is one.
+    pub fn load(&mut self, board: &Board, track_winner: bool) {
+        let dims = board.dimensions();
+        self.dims = dims;
+        self.width = dims.x as usize + 2 * BORDER;
+        let height = dims.y as usize + 2 * BORDER;
+        for (i, &(dx, dy)) in NEIGHBORS.iter().enumerate() {
+            self.neighbors[i] = dy * self.width as isize + dx;
+        }
+
+        self.cells.clear();
+        self.empty.clear();
+        self.empty_idx.clear();
+        self.empty_idx.resize(self.width * height, 0);
+        for idx in 0..self.width * height {
+            let pos = self.pos_of(idx);
+            self.cells.push(board.get(pos));
+            if board.on_board(pos) && board.is_empty(pos) {
+                self.empty_idx[idx] = self.empty.len();
+                self.empty.push(idx);
+            }
+        }
+        self.to_play = board.to_play();
+        self.last_move = board.last_move();
+        self.winner = board.winner();
+
+        self.parents.clear();
+        if track_winner {
+            self.parents.extend(0..self.width * height);
+            // each edge is one group, and the stones join it as if played now
+            for idx in 0..self.width * height {
+                let pos = self.pos_of(idx);
+                if self.cells[idx].is_some() && !self.is_border(pos) {
+                    self.join(idx);
+                }
+            }
+        }
+    }
+
+    pub fn dimensions(&self) -> Pos {
+        self.dims
+    }
+
+    pub fn is_empty(&self, pos: Pos) -> bool {
+        self.get(pos).is_none()
+    }
+
+    /// The number of empty cells.
+    pub fn num_empty(&self) -> usize {
+        self.empty.len()
+    }
+
+    /// One of the empty cells, indexed from zero to `num_empty`. Playing a move changes the order.
+    pub fn empty_cell(&self, i: usize) -> Pos {
+        self.pos_of(self.empty[i])
+    }
+
+    /// Play a stone for the player to move. Returns false if the cell is filled or off the board.
+    pub fn play(&mut self, pos: Pos) -> bool {
+        if !self.on_board(pos) {
+            return false;
+        }
+        let idx = self.idx_of(pos);
+        if self.cells[idx].is_some() {
+            return false;
+        }
+        let color = self.to_play;
+        self.cells[idx] = Some(color);
+
+        let i = self.empty_idx[idx];
+        let moved = *self.empty.last().unwrap();
+        self.empty.swap_remove(i);
+        self.empty_idx[moved] = i;
+
+        self.to_play = color.invert();
+        self.last_move = Move::new(color, pos);
+        if !self.parents.is_empty() {
+            self.join(idx);
+        }
+        true
+    }
+
+    /// Whether the rollout is over: the board is full, or a player has connected their edges
+    /// and the winner is tracked.
+    pub fn is_over(&self) -> bool {
+        self.empty.is_empty() || self.winner.is_some()
+    }
+
+    /// The winner, if there is one yet. Unless the winner is tracked, this searches the board,
+    /// so it should only be asked for once the board is full.
+    pub fn winner(&self) -> Option<Color> {
+        if !self.parents.is_empty() || self.winner.is_some() {
+            return self.winner;
+        }
+        [Color::Black, Color::White].iter().cloned().find(|&color| self.connects(color))
+    }
+
+    pub fn iter_filled<'a>(&'a self) -> impl Iterator<Item = Move> + 'a {
+        self.cells.iter().enumerate().filter_map(move |(idx, &cell)| {
+            let pos = self.pos_of(idx);
+            match cell {
+                Some(color) if self.on_board(pos) => Some(Move::new(color, pos)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Check whether `color` has a chain of stones between its edges, by flood filling from the
+    /// edge just off the board on one side.
+    fn connects(&self, color: Color) -> bool {
+        let (start, end) = self.edges(color);
+        let mut seen = vec![false; self.cells.len()];
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(idx) = stack.pop() {
+            if idx == end {
+                return true;
+            }
+            for &offset in &self.neighbors {
+                let n = (idx as isize + offset) as usize;
+                if !seen[n] && self.cells[n] == Some(color) {
+                    seen[n] = true;
+                    // the outer row of the border has neighbors outside of `cells`
+                    if !self.is_border(self.pos_of(n)) {
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Cells of the two edges of a color, just off the board next to the first row or column.
+    fn edges(&self, color: Color) -> (usize, usize) {
+        match color {
+            Color::Black => (self.idx_of(Pos::new(0, -1)), self.idx_of(Pos::new(0, self.dims.y))),
+            Color::White => (self.idx_of(Pos::new(-1, 0)), self.idx_of(Pos::new(self.dims.x, 0))),
+        }
+    }
+
+    /// Join a stone to its neighbors of the same color, and record the winner if that connects
+    /// its edges.
+    fn join(&mut self, idx: usize) {
+        let color = self.cells[idx].unwrap();
+        for i in 0..6 {
+            let n = (idx as isize + self.neighbors[i]) as usize;
+            if self.cells[n] == Some(color) {
+                let (a, b) = (self.find(idx), self.find(n));
+                self.parents[a] = b;
+            }
+        }
+        let (start, end) = self.edges(color);
+        if self.find(start) == self.find(end) {
+            self.winner = Some(color);
+        }
+    }
+
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parents[idx] != idx {
+            self.parents[idx] = self.parents[self.parents[idx]];
+            idx = self.parents[idx];
+        }
+        idx
+    }
+
+    /// Whether a position is in the outer row of the border, or beyond it.
+    fn is_border(&self, pos: Pos) -> bool {
+        let border = BORDER as i16 - 1;
+        pos.x < -border || pos.y < -border || pos.x >= self.dims.x + border ||
+        pos.y >= self.dims.y + border
+    }
+
+    /// The index in `cells` of a position within the border.
+    fn idx_of(&self, pos: Pos) -> usize {
+        (pos.y as isize + BORDER as isize) as usize * self.width +
+        (pos.x as isize + BORDER as isize) as usize
+    }
+
+    fn pos_of(&self, idx: usize) -> Pos {
+        Pos::new((idx % self.width) as i16 - BORDER as i16,
+                 (idx / self.width) as i16 - BORDER as i16)
+    }
+}
+
+impl BoardView for PlayoutBoard {
+    fn get(&self, pos: Pos) -> Option<Color> {
+        let border = BORDER as i16;
+        if pos.x < -border || pos.y < -border || pos.x >= self.dims.x + border ||
+           pos.y >= self.dims.y + border {
+            // beyond the border, the edges continue as on a `Board`
+            let mut board_pos = pos;
+            board_pos.x = board_pos.x.max(-border).min(self.dims.x + border - 1);
+            board_pos.y = board_pos.y.max(-border).min(self.dims.y + border - 1);
+            return self.cells[self.idx_of(board_pos)];
+        }
+        self.cells[self.idx_of(pos)]
+    }
+
+    fn on_board(&self, pos: Pos) -> bool {
+        pos.x >= 0 && pos.y >= 0 && pos.x < self.dims.x && pos.y < self.dims.y
+    }
+
+    fn to_play(&self) -> Color {
+        self.to_play
+    }
+
+    fn last_move(&self) -> Move {
+        self.last_move
+    }
+}
+
+#[test]
+fn test_playout_board() {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    for &dims in &[(1, 1), (5, 5), (7, 4), (3, 9), (11, 11)] {
+        for _ in 0..50 {
+            let mut board = Board::new(dims);
+            let setup = rng.gen_range(0, board.dimensions().area() / 2 + 1);
+            for _ in 0..setup {
+                let cells: Vec<Pos> = board.iter_empty().collect();
+                let to_play = board.to_play();
+                board.play(Move::new(to_play, *rng.choose(&cells).unwrap()));
+            }
+
+            // the cells match everywhere, even beyond the border
+            let mut filled = PlayoutBoard::new(&board, false);
+            let mut tracked = PlayoutBoard::new(&board, true);
+            for x in -4..dims.0 + 4 {
+                for y in -4..dims.1 + 4 {
+                    let pos = Pos::new(x, y);
+                    assert_eq!(BoardView::get(&filled, pos), board.get(pos));
+                }
+            }
+            assert_eq!(tracked.winner(), board.winner());
+
+            // play the same random game on all boards, and the winners agree
+            while filled.num_empty() > 0 {
+                let pos = filled.empty_cell(rng.gen_range(0, filled.num_empty()));
+                let to_play = board.to_play();
+                assert!(filled.play(pos));
+                assert!(tracked.play(pos));
+                assert!(board.play(Move::new(to_play, pos)));
+                assert!(!filled.play(pos));
+                assert_eq!(tracked.winner(), board.winner());
+                assert_eq!(BoardView::last_move(&filled), board.last_move());
+            }
+            assert!(board.winner().is_some());
+            assert_eq!(filled.winner(), board.winner());
+            assert_eq!(filled.iter_filled().count(), board.iter_filled().count());
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+use super::board::{Board, Color, Move, Pos, Coord};
+
+/// A property of an SGF node, such as `B[a1]` or `AB[a1][b2]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub ident: String,
+    pub values: Vec<String>,
+}
+
+/// The main line of a Hex (GM[11]) game record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub dims: Pos,
+    /// Root properties other than the board size and setup stones, such as player names.
+    pub info: Vec<Property>,
+    /// Stones placed with AB and AW before the first move.
+    pub setup: Vec<Move>,
+    pub moves: Vec<Move>,
+}
+
+impl Game {
+    pub fn new(dims: Pos, moves: Vec<Move>) -> Game {
+        Game {
+            dims: dims,
+            info: Vec::new(),
+            setup: Vec::new(),
+            moves: moves,
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Game, String> {
+        let mut text = String::new();
+        try!(File::open(path)
+                 .and_then(|mut f| f.read_to_string(&mut text))
+                 .map_err(|e| format!("cannot read {}: {}", path, e)));
+        Game::parse(&text)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        File::create(path)
+            .and_then(|mut f| write!(f, "{}", self))
+            .map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    /// Parse the main line of the first game in an SGF collection. Variations are ignored.
+    pub fn parse(text: &str) -> Result<Game, String> {
+        let mut parser = Parser { chars: text.chars().peekable() };
+        let nodes = try!(parser.game_tree());
+
+        let mut game = Game::new(Pos::new(11, 11), Vec::new());
+        for (i, node) in nodes.into_iter().enumerate() {
+            for prop in node {
+                match &prop.ident[..] {
+                    "GM" if prop.values[0] != "11" => return Err("not a Hex game".into()),
+                    "FF" | "GM" | "AP" => (), // written out again by the serializer
+                    "SZ" if i == 0 => game.dims = try!(parse_size(&prop.values[0])),
+                    "B" | "W" | "AB" | "AW" => {
+                        let color = if prop.ident.starts_with('A') {
+                            &prop.ident[1..]
+                        } else {
+                            &prop.ident[..]
+                        };
+                        let color = color.parse().unwrap();
+                        for value in &prop.values {
+                            let m = try!(Move::parse(color, value)
+                                             .map_err(|_| format!("invalid move: {}", value)));
+                            if !prop.ident.starts_with('A') {
+                                game.moves.push(m);
+                            } else if game.moves.is_empty() {
+                                game.setup.push(m);
+                            } else {
+                                return Err("setup stones after a move are not supported".into());
+                            }
+                        }
+                    }
+                    _ if i == 0 => game.info.push(prop),
+                    _ => (),
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    /// Set up a board with the setup stones and the first `num_moves` moves.
+    pub fn board(&self, num_moves: usize) -> Result<Board, String> {
+        let mut board = Board::new(self.dims);
+        if !board.set_up(&self.setup) {
+            return Err("invalid setup stones in game record".into());
+        }
+        for &m in self.moves.iter().take(num_moves) {
+            if !board.play(m) {
+                return Err(format!("illegal move in game record: {}", m));
+            }
+        }
+        Ok(board)
+    }
+}
+
+fn parse_size(size: &str) -> Result<Pos, String> {
+    let mut dims = size.split(':').map(|x| x.trim().parse::<Coord>());
+    match (dims.next(), dims.next(), dims.next()) {
+        (Some(Ok(n)), None, None) if n > 0 => Ok(Pos::new(n, n)),
+        (Some(Ok(cols)), Some(Ok(rows)), None) if cols > 0 && rows > 0 => {
+            Ok(Pos::new(cols, rows))
+        }
+        _ => Err(format!("invalid board size: {}", size)),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace("\\", "\\\\").replace("]", "\\]")
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "(;FF[4]GM[11]AP[coronene]"));
+        if self.dims.x == self.dims.y {
+            try!(write!(f, "SZ[{}]", self.dims.x));
+        } else {
+            try!(write!(f, "SZ[{}:{}]", self.dims.x, self.dims.y));
+        }
+        for prop in &self.info {
+            try!(write!(f, "{}", prop.ident));
+            for value in &prop.values {
+                try!(write!(f, "[{}]", escape(value)));
+            }
+        }
+        for &color in &[Color::Black, Color::White] {
+            let stones: Vec<_> = self.setup.iter().filter(|m| m.color() == Some(color)).collect();
+            if !stones.is_empty() {
+                try!(write!(f, "\nA{}", color));
+                for m in stones {
+                    try!(write!(f, "[{}]", m.to_string().to_lowercase()));
+                }
+            }
+        }
+
+        // passes and resignations are not tagged with a color, so assume moves alternate
+        let mut to_play = Color::Black;
+        for m in &self.moves {
+            let color = m.color().unwrap_or(to_play);
+            try!(write!(f, "\n;{}[{}]", color, m.to_string().to_lowercase()));
+            to_play = color.invert();
+        }
+        write!(f, ")\n")
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().map_or(false, |c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}' but found '{}'", expected, c)),
+            None => Err(format!("expected '{}' but found end of file", expected)),
+        }
+    }
+
+    /// Parse a game tree, returning the nodes of its main line.
+    fn game_tree(&mut self) -> Result<Vec<Vec<Property>>, String> {
+        try!(self.expect('('));
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek().cloned() {
+                Some(';') => {
+                    self.chars.next();
+                    nodes.push(try!(self.node()));
+                }
+                _ => break,
+            }
+        }
+        if nodes.is_empty() {
+            return Err("empty game tree".into());
+        }
+
+        // only the first variation is part of the main line
+        let mut first = true;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek().cloned() {
+                Some('(') => {
+                    let variation = try!(self.game_tree());
+                    if first {
+                        nodes.extend(variation);
+                        first = false;
+                    }
+                }
+                _ => break,
+            }
+        }
+        try!(self.expect(')'));
+        Ok(nodes)
+    }
+
+    fn node(&mut self) -> Result<Vec<Property>, String> {
+        let mut props = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let mut ident = String::new();
+            while let Some(c) = self.chars.peek().cloned() {
+                if !c.is_ascii_uppercase() {
+                    break;
+                }
+                ident.push(c);
+                self.chars.next();
+            }
+            if ident.is_empty() {
+                return Ok(props);
+            }
+            let mut values = Vec::new();
+            loop {
+                self.skip_whitespace();
+                if self.chars.peek() != Some(&'[') {
+                    break;
+                }
+                self.chars.next();
+                values.push(try!(self.value()));
+            }
+            if values.is_empty() {
+                return Err(format!("property {} has no value", ident));
+            }
+            props.push(Property {
+                ident: ident,
+                values: values,
+            });
+        }
+    }
+
+    fn value(&mut self) -> Result<String, String> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(']') => return Ok(value.trim().into()),
+                Some('\\') => {
+                    match self.chars.next() {
+                        Some(c) => value.push(c),
+                        None => break,
+                    }
+                }
+                Some(c) => value.push(c),
+                None => break,
+            }
+        }
+        Err("unterminated property value".into())
+    }
+}
+
+#[test]
+fn test_sgf_round_trip() {
+    let text = "(;FF[4]GM[11]SZ[9:7]PB[black\\]player]\nAB[a1][b2]AW[c3]\n;B[d4]\
+                (;W[swap-pieces];B[e5]\n;W[pass];B[resign])(;W[f6]))";
+    let game = Game::parse(text).unwrap();
+    assert_eq!(game.dims, Pos::new(9, 7));
+    assert_eq!(game.setup.len(), 3);
+    assert_eq!(game.moves.len(), 5);
+    assert_eq!(game.moves[3], Move::None);
+    assert_eq!(game.moves[4], Move::Resign);
+    assert_eq!(game.info.iter().find(|p| p.ident == "PB").unwrap().values[0], "black]player");
+    assert_eq!(Game::parse(&game.to_string()), Ok(game.clone()));
+
+    let board = game.board(1).unwrap();
+    assert_eq!(board.get((3, 3)), Some(Color::Black));
+    assert_eq!(board.get((2, 2)), Some(Color::White));
+    assert!(game.board(2).is_err()); // no swap after setup stones
+    assert!(Game::parse("(;FF[4]GM[11]SZ[9];B[a1]AW[b2];W[c3])").is_err());
+}
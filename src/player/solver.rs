@@ -0,0 +1,356 @@
+//! Depth-first proof-number search (DFPN), which proves who wins a position.
+//!
+//! Each position has a proof number, the least number of positions which must still be shown to
+//! be won for the player to move to prove that they win, and a disproof number, the same for
+//! showing that they lose. The search always works on the most proving child, and only returns to
+//! the parent once the child's numbers pass thresholds derived from the parent's, which keeps the
+//! memory needed down to a transposition table. Positions are also decided early when a player is
+//! connected through bridges and edge templates, and inferior cells are never played. H-search
+//! finds many more connections, but it is too slow for every position, so it is only used for the
+//! position being solved and the positions right after it.
+//!
+//! The transposition table is limited in size. Once it is full, the half of the positions which
+//! took the least searching to get their numbers are forgotten, and searched again if needed.
+
+use std::collections::HashMap;
+use std::mem;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use time;
+use super::board::{Board, Color, Move, Pos};
+use super::inferior::InferiorCells;
+use super::templates;
+use super::vc::{Point, VcSet};
+
+/// A proof or disproof number which can't be reached: the position is decided the other way.
+const INFINITY: u64 = 1 << 40;
+
+/// How many positions are searched between checks of the time limit.
+const CHECK_INTERVAL: usize = 1024;
+
+/// What the transposition table knows about a position.
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    phi: u64,
+    delta: u64,
+    /// How many positions have been searched to find the numbers, which is the work lost if the
+    /// entry is forgotten.
+    work: usize,
+}
+
+/// The outcome of a position which has been solved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub winner: Color,
+    /// A move which wins for the player to move, if they are the winner.
+    pub best_move: Option<Move>,
+}
+
+/// Solve a position, giving up once `max_time` seconds have passed or `max_nodes` positions have
+/// been searched, unless it is zero. The transposition table is kept within about `max_memory`
+/// bytes. Swaps are not considered.
+pub fn solve(board: &Board,
+             max_time: f32,
+             max_nodes: usize,
+             max_memory: usize)
+             -> Option<Solution> {
+    if let Some(winner) = board.winner() {
+        return Some(Solution {
+            winner: winner,
+            best_move: None,
+        });
+    }
+    let vcs = [VcSet::new(board, Color::Black), VcSet::new(board, Color::White)];
+    if connection_winner(board, &vcs) == Some((INFINITY, 0)) {
+        return Some(Solution {
+            winner: board.to_play().invert(),
+            best_move: None,
+        });
+    }
+    let mut solver = Solver::new(board, max_time, max_nodes, max_memory);
+    let mut board = board.snapshot();
+    let hash = solver.hash(&board);
+    let (phi, delta) = solver.mid(&mut board, hash, Some(&vcs), INFINITY, INFINITY);
+    eprintln!("Searched {} positions in {:.2}s",
+              solver.nodes,
+              time::precise_time_s() - solver.start_time);
+    let to_play = board.to_play();
+    if phi == 0 {
+        // one of the children must have been disproven for the opponent
+        let best_move = solver.moves(&board)
+                              .into_iter()
+                              .find(|&pos| {
+                                  let child = solver.child_hash(&board, hash, pos);
+                                  solver.table.get(&child).map_or(false, |e| e.delta == 0)
+                              })
+                              .map(|pos| Move::new(to_play, pos));
+        Some(Solution {
+            winner: to_play,
+            best_move: best_move,
+        })
+    } else if delta == 0 {
+        Some(Solution {
+            winner: to_play.invert(),
+            best_move: None,
+        })
+    } else {
+        None
+    }
+}
+
+struct Solver {
+    /// Proof and disproof numbers of the positions searched so far, by hash.
+    table: HashMap<u64, Entry>,
+    /// How many entries the table may hold before some are forgotten.
+    max_entries: usize,
+    /// Random numbers for each color in each cell, and for White being the player to move, which
+    /// are combined into the hash of a position.
+    zobrist: Vec<[u64; 2]>,
+    white_to_play: u64,
+    dims: Pos,
+    nodes: usize,
+    max_nodes: usize,
+    /// Whether a limit has been reached, so that the search is unwinding.
+    stopped: bool,
+    start_time: f64,
+    max_time: f32,
+}
+
+impl Solver {
+    fn new(board: &Board, max_time: f32, max_nodes: usize, max_memory: usize) -> Solver {
+        let dims = board.dimensions();
+        let mut rng = XorShiftRng::from_seed([0x1234_5678, 0x9abc_def0, 0x0fed_cba9, 0x8765_4321]);
+        Solver {
+            table: HashMap::new(),
+            // the table needs about twice the size of its entries, allowing for empty buckets
+            max_entries: (max_memory / (2 * mem::size_of::<(u64, Entry)>())).max(1),
+            zobrist: (0..dims.area()).map(|_| [rng.gen(), rng.gen()]).collect(),
+            white_to_play: rng.gen(),
+            dims: dims,
+            nodes: 0,
+            max_nodes: max_nodes,
+            stopped: false,
+            start_time: time::precise_time_s(),
+            max_time: max_time,
+        }
+    }
+
+    /// Search a position until its proof number reaches `phi_limit` or its disproof number
+    /// reaches `delta_limit`, or the search runs out of time or positions, and return them. The
+    /// virtual connections of Black and White are only given for the root.
+    fn mid(&mut self,
+           board: &mut Board,
+           hash: u64,
+           vcs: Option<&[VcSet; 2]>,
+           phi_limit: u64,
+           delta_limit: u64)
+           -> (u64, u64) {
+        self.nodes += 1;
+        let start_nodes = self.nodes;
+        if self.table.len() >= self.max_entries {
+            self.forget();
+        }
+        // the root must be searched even if it is already decided, to find a winning move
+        if vcs.is_none() {
+            if let Some(numbers) = self.evaluate(board) {
+                self.store(hash, numbers, 0);
+                return numbers;
+            }
+        }
+
+        let moves = self.moves(board);
+        let children: Vec<(Pos, u64)> = moves.into_iter()
+                                             .map(|pos| (pos, self.child_hash(board, hash, pos)))
+                                             .collect();
+        // decided children are recognized as soon as they are generated
+        for &(pos, child) in &children {
+            if !self.table.contains_key(&child) {
+                let m = Move::new(board.to_play(), pos);
+                board.make_move(m);
+                let numbers = self.evaluate(board)
+                                  .or_else(|| {
+                                      vcs.and_then(|vcs| {
+                                          let mut vcs = vcs.clone();
+                                          for vc in &mut vcs {
+                                              vc.play(board, m);
+                                          }
+                                          connection_winner(board, &vcs)
+                                      })
+                                  })
+                                  .unwrap_or((1, 1));
+                board.undo();
+                self.store(child, numbers, 0);
+            }
+        }
+
+        loop {
+            // our proof number is the smallest disproof number of a child, and our disproof number
+            // the sum of their proof numbers
+            let (mut phi, mut delta) = (INFINITY, 0);
+            let (mut best, mut best_delta, mut second_delta) = (0, INFINITY, INFINITY);
+            for (i, &(_, child)) in children.iter().enumerate() {
+                let (child_phi, child_delta) = self.numbers(child);
+                delta = (delta + child_phi).min(INFINITY);
+                if child_delta < best_delta {
+                    second_delta = best_delta;
+                    best_delta = child_delta;
+                    best = i;
+                } else if child_delta < second_delta {
+                    second_delta = child_delta;
+                }
+                phi = phi.min(child_delta);
+            }
+            if phi >= phi_limit || delta >= delta_limit || self.out_of_budget() {
+                let work = self.table.get(&hash).map_or(0, |e| e.work) + self.nodes - start_nodes;
+                self.store(hash, (phi, delta), work);
+                return (phi, delta);
+            }
+
+            // search the most proving child until it is no longer the best, or we reach a limit
+            let (pos, child) = children[best];
+            let (child_phi, _) = self.numbers(child);
+            let child_phi_limit = delta_limit - delta + child_phi;
+            let child_delta_limit = phi_limit.min(second_delta.saturating_add(1));
+            board.make_move(Move::new(board.to_play(), pos));
+            self.mid(board, child, None, child_phi_limit, child_delta_limit);
+            board.undo();
+        }
+    }
+
+    /// The proof and disproof numbers of a position, which are both one if it hasn't been
+    /// searched or has been forgotten.
+    fn numbers(&self, hash: u64) -> (u64, u64) {
+        self.table.get(&hash).map_or((1, 1), |e| (e.phi, e.delta))
+    }
+
+    fn store(&mut self, hash: u64, (phi, delta): (u64, u64), work: usize) {
+        self.table.insert(hash,
+                          Entry {
+                              phi: phi,
+                              delta: delta,
+                              work: work,
+                          });
+    }
+
+    /// Forget the half of the table which took the least work to fill in.
+    fn forget(&mut self) {
+        let mut work: Vec<usize> = self.table.values().map(|e| e.work).collect();
+        let middle = work.len() / 2;
+        let threshold = *work.select_nth_unstable(middle).1;
+        self.table.retain(|_, e| e.work > threshold);
+    }
+
+    /// The proof and disproof numbers of a position which is already decided.
+    fn evaluate(&self, board: &Board) -> Option<(u64, u64)> {
+        templates::virtual_winner(board).map(|winner| {
+            if winner == board.to_play() {
+                (0, INFINITY)
+            } else {
+                (INFINITY, 0)
+            }
+        })
+    }
+
+    /// The moves worth considering: every empty cell which isn't inferior.
+    fn moves(&self, board: &Board) -> Vec<Pos> {
+        let inferior = InferiorCells::new(board);
+        let moves: Vec<Pos> = board.iter_empty()
+                                   .filter(|&pos| !inferior.is_inferior(pos))
+                                   .collect();
+        if moves.is_empty() {
+            board.iter_empty().collect()
+        } else {
+            moves
+        }
+    }
+
+    fn hash(&self, board: &Board) -> u64 {
+        let mut hash = 0;
+        for m in board.iter_filled() {
+            if let Move::Play { color, pos } = m {
+                hash ^= self.zobrist[self.idx(pos)][color as usize];
+            }
+        }
+        if board.to_play() == Color::White {
+            hash ^= self.white_to_play;
+        }
+        hash
+    }
+
+    /// The hash of the position after the player to move plays at `pos`.
+    fn child_hash(&self, board: &Board, hash: u64, pos: Pos) -> u64 {
+        hash ^ self.zobrist[self.idx(pos)][board.to_play() as usize] ^ self.white_to_play
+    }
+
+    fn idx(&self, pos: Pos) -> usize {
+        pos.y as usize * self.dims.x as usize + pos.x as usize
+    }
+
+    fn out_of_budget(&mut self) -> bool {
+        if !self.stopped {
+            self.stopped = (self.max_nodes > 0 && self.nodes >= self.max_nodes) ||
+                           (self.nodes % CHECK_INTERVAL == 0 &&
+                            time::precise_time_s() - self.start_time >= self.max_time as f64);
+        }
+        self.stopped
+    }
+}
+
+/// The proof and disproof numbers of a position which is decided by the virtual connections of
+/// either player: the player to move wins with a full or a semi connection between their edges,
+/// and the opponent with a full one.
+fn connection_winner(board: &Board, vcs: &[VcSet; 2]) -> Option<(u64, u64)> {
+    let to_play = board.to_play();
+    let (ours, theirs) = (&vcs[to_play as usize], &vcs[to_play.invert() as usize]);
+    if ours.winning_connection().is_some() ||
+       !ours.semi_connections(Point::Edge(0), Point::Edge(1)).is_empty() {
+        Some((0, INFINITY))
+    } else if theirs.winning_connection().is_some() {
+        Some((INFINITY, 0))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_solve() {
+    use super::board;
+
+    // compare with searching every line on small random positions
+    let mut rng = XorShiftRng::from_seed([4, 3, 2, 1]);
+    for &dims in &[(3, 3), (4, 3), (4, 4), (5, 4)] {
+        for _ in 0..20 {
+            let mut board = Board::new(dims);
+            // searching every line is only feasible with a few empty cells
+            let setup = board.dimensions().area() - rng.gen_range(3, 10);
+            for _ in 0..setup {
+                let cells: Vec<Pos> = board.iter_empty().collect();
+                let to_play = board.to_play();
+                board.play(Move::new(to_play, *rng.choose(&cells).unwrap()));
+            }
+            if board.winner().is_some() {
+                continue;
+            }
+            // a table much too small for the search must only slow it down
+            let max_memory = if rng.gen() { 1 << 20 } else { 2000 };
+            let solution = solve(&board, f32::INFINITY, 0, max_memory).unwrap();
+            assert_eq!(solution.winner, board::solve(&board));
+            // positions decided by H-search must be decided the same way
+            let vcs = [VcSet::new(&board, Color::Black), VcSet::new(&board, Color::White)];
+            if let Some((phi, _)) = connection_winner(&board, &vcs) {
+                let to_play = board.to_play();
+                assert_eq!(solution.winner, if phi == 0 { to_play } else { to_play.invert() });
+            }
+            if solution.winner == board.to_play() {
+                let mut next = board.snapshot();
+                next.play(solution.best_move.unwrap());
+                assert!(next.winner() == Some(solution.winner) ||
+                        board::solve(&next) == solution.winner);
+            } else {
+                assert_eq!(solution.best_move, None);
+            }
+        }
+    }
+
+    // the limits are respected
+    assert_eq!(solve(&Board::new((7, 7)), f32::INFINITY, 100, 1 << 20), None);
+}
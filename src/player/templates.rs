@@ -0,0 +1,202 @@
+//! Fast detection of positions which are already won, using bridges and edge templates.
+//!
+//! H-search in `vc` finds many more connections, but it is far too slow to run on every node of
+//! the search tree. Here a player's groups are linked to each other by bridges, and to their edges
+//! by edge templates, taking links greedily as long as their carriers are disjoint from the
+//! carriers of the links already taken. If the links join the two edges, the player has won:
+//! whatever the opponent plays intrudes into at most one carrier, and answering inside that
+//! carrier restores the link.
+
+use union_find::{UnionFind, UnionBySize, QuickUnionUf};
+use super::board::{Board, Color, Pos};
+use super::misc::bitset::BitSet;
+
+/// Bridges as (carrier, carrier, other end), relative to a stone.
+const BRIDGES: &'static [((i16, i16), (i16, i16), (i16, i16))] = &[((-1, 0), (0, -1), (-1, -1)),
+                                                                   ((0, -1), (1, -1), (1, -2)),
+                                                                   ((1, -1), (1, 0), (2, -1)),
+                                                                   ((1, 0), (0, 1), (1, 1)),
+                                                                   ((0, 1), (-1, 1), (-1, 2)),
+                                                                   ((-1, 1), (-1, 0), (-2, 1))];
+
+/// Edge templates as (row of the stone, carrier). Rows count from zero at the edge, and the
+/// carrier is relative to the stone, with the edge towards negative y. Each template is also used
+/// mirrored.
+const TEMPLATES: &'static [(i16, &'static [(i16, i16)])] =
+    &[// II
+      (1, &[(0, -1), (1, -1)]),
+      // IIIa, the ziggurat
+      (2, &[(-1, 0), (-1, -1), (0, -1), (1, -1), (-1, -2), (0, -2), (1, -2), (2, -2)]),
+      // IVa
+      (3,
+       &[(1, 0), (-1, -1), (0, -1), (1, -1), (2, -1), (3, -1), (-1, -2), (0, -2), (1, -2),
+         (2, -2), (3, -2), (4, -2), (-1, -3), (0, -3), (1, -3), (2, -3), (3, -3), (4, -3),
+         (5, -3)])];
+
+/// Return the player who has won or can no longer be stopped from winning, if any.
+pub fn virtual_winner(board: &Board) -> Option<Color> {
+    if board.winner().is_some() {
+        return board.winner();
+    }
+    [Color::Black, Color::White].iter().cloned().find(|&color| connects_edges(board, color))
+}
+
+/// Check whether the bridges and edge templates of `color` join its two edges.
+pub fn connects_edges(board: &Board, color: Color) -> bool {
+    let dims = board.dimensions();
+    let area = dims.area();
+    let idx = |pos: Pos| pos.y as usize * dims.x as usize + pos.x as usize;
+    let stones: Vec<Pos> = board.iter_filled()
+                                .filter(|m| m.color() == Some(color))
+                                .filter_map(|m| m.pos())
+                                .collect();
+    let mut groups = QuickUnionUf::<UnionBySize>::new(area + 2);
+    let mut used = BitSet::new(area);
+
+    // the carrier of a link is usable if its cells are empty and not part of another link
+    let usable = |used: &BitSet, cells: &[Pos]| {
+        cells.iter().all(|&pos| {
+            board.on_board(pos) && board.is_empty(pos) && !used.contains(idx(pos))
+        })
+    };
+
+    // adjacent stones and stones on the edges are connected through nothing at all
+    let neighbor_patterns = &[(-1, 0), (0, -1), (-1, 1), (0, 1), (1, 0), (1, -1)];
+    for &pos in &stones {
+        for &pat in neighbor_patterns {
+            let n = pos + pat.into();
+            if board.on_board(n) && board.get(n) == Some(color) {
+                groups.union(idx(pos), idx(n));
+            }
+        }
+        for edge in 0..2 {
+            if to_frame(dims, color, edge, pos).y == 0 {
+                groups.union(idx(pos), area + edge);
+            }
+        }
+    }
+
+    for &pos in &stones {
+        for &(a, b, end) in BRIDGES {
+            let end = pos + end.into();
+            let carrier = [pos + a.into(), pos + b.into()];
+            if board.on_board(end) && board.get(end) == Some(color) &&
+               groups.find(idx(pos)) != groups.find(idx(end)) && usable(&used, &carrier) {
+                groups.union(idx(pos), idx(end));
+                for &c in &carrier {
+                    used.insert(idx(c));
+                }
+            }
+        }
+    }
+
+    for &pos in &stones {
+        for edge in 0..2 {
+            let stone = to_frame(dims, color, edge, pos);
+            for &(row, template) in TEMPLATES {
+                if stone.y != row || groups.find(idx(pos)) == groups.find(area + edge) {
+                    continue;
+                }
+                for &mirror in &[false, true] {
+                    let carrier: Vec<Pos> = template.iter()
+                                                    .map(|&(dx, dy)| {
+                                                        let dx = if mirror { -dx - dy } else { dx };
+                                                        let cell = Pos::new(stone.x + dx,
+                                                                            stone.y + dy);
+                                                        from_frame(dims, color, edge, cell)
+                                                    })
+                                                    .collect();
+                    if usable(&used, &carrier) {
+                        groups.union(idx(pos), area + edge);
+                        for &c in &carrier {
+                            used.insert(idx(c));
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    groups.find(area) == groups.find(area + 1)
+}
+
+/// Convert a position to coordinates relative to one of the edges of `color`, in which that edge
+/// runs along the top, so that y is the distance from it. The board is reflected in the long
+/// diagonal for White and rotated for the far edge, which both preserve adjacency.
+fn to_frame(dims: Pos, color: Color, edge: usize, pos: Pos) -> Pos {
+    let (dims, pos) = match color {
+        Color::Black => (dims, pos),
+        Color::White => (Pos::new(dims.y, dims.x), Pos::new(pos.y, pos.x)),
+    };
+    if edge == 0 {
+        pos
+    } else {
+        Pos::new(dims.x - 1 - pos.x, dims.y - 1 - pos.y)
+    }
+}
+
+/// The inverse of `to_frame`.
+fn from_frame(dims: Pos, color: Color, edge: usize, pos: Pos) -> Pos {
+    let frame_dims = match color {
+        Color::Black => dims,
+        Color::White => Pos::new(dims.y, dims.x),
+    };
+    let pos = if edge == 0 {
+        pos
+    } else {
+        Pos::new(frame_dims.x - 1 - pos.x, frame_dims.y - 1 - pos.y)
+    };
+    match color {
+        Color::Black => pos,
+        Color::White => Pos::new(pos.y, pos.x),
+    }
+}
+
+#[test]
+fn test_virtual_winner() {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use super::board::{solve, Move};
+
+    // bridges to both edges from the middle of a 3x3 board, and ziggurats on a 5x5 board
+    for &size in &[3, 5] {
+        let mut board = Board::new((size, size));
+        board.play(Move::new(Color::White, (size / 2, size / 2)));
+        assert_eq!(virtual_winner(&board), Some(Color::White));
+        board.play(Move::new(Color::Black, (size / 2 - 1, size / 2)));
+        assert_eq!(virtual_winner(&board), None);
+    }
+
+    // template IVa from the fourth row, which needs a wider board, for both colors
+    for &color in &[Color::Black, Color::White] {
+        let frame = |x, y| if color == Color::Black { (x, y) } else { (y, x) };
+        let mut board = Board::new(frame(9, 7));
+        board.play(Move::new(color, frame(4, 3)));
+        assert_eq!(virtual_winner(&board), Some(color));
+        board.play(Move::new(color.invert(), frame(4, 4)));
+        assert_eq!(virtual_winner(&board), None);
+    }
+
+    // whatever is found must be a real win, even with the opponent to move
+    let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+    let mut found = 0;
+    for _ in 0..300 {
+        let mut board = Board::new((4, 4));
+        let mut cells: Vec<Pos> = board.iter_empty().collect();
+        rng.shuffle(&mut cells);
+        let stones = rng.gen_range(6, 10);
+        for (i, &pos) in cells.iter().take(stones).enumerate() {
+            board.play(Move::new(Color::from(i % 2 == 1), pos));
+        }
+        if board.winner().is_some() {
+            continue;
+        }
+        if let Some(winner) = virtual_winner(&board) {
+            found += 1;
+            board.set_to_play(winner.invert());
+            assert_eq!(solve(&board), winner, "\n{}", board);
+        }
+    }
+    assert!(found > 10);
+}
+
@@ -0,0 +1,198 @@
+use super::board::Color;
+
+/// Number of our own moves expected per empty cell. Hex games rarely fill the board, so we plan
+/// for about half of the remaining plies.
+const MOVES_PER_EMPTY_CELL: f32 = 0.25;
+/// Never plan for fewer than this many remaining moves while in main time.
+const MIN_MOVES_LEFT: f32 = 8.0;
+/// Never spend more than this fraction of the remaining main time on a single move.
+const MAX_MAIN_FRACTION: f32 = 0.2;
+/// Extra weight given to moves in the middlegame, relative to the opening and endgame.
+const MIDDLEGAME_BOOST: f32 = 1.0;
+/// Fraction of the board filled at which the middlegame is considered most complex.
+const MIDDLEGAME_PEAK: f32 = 0.2;
+/// Width of the middlegame, as a fraction of the board.
+const MIDDLEGAME_WIDTH: f32 = 0.12;
+/// Seconds kept in reserve on every move to cover communication lag.
+const SAFETY_MARGIN: f32 = 0.1;
+
+/// Clock settings for a game, as given by the `time_settings` HTP command.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimeSettings {
+    /// Main time in seconds.
+    pub main_time: f32,
+    /// Length of each byoyomi period in seconds. Zero means sudden death.
+    pub byoyomi_time: f32,
+    /// Number of stones which must be played in each byoyomi period.
+    pub byoyomi_stones: u32,
+}
+
+impl TimeSettings {
+    /// Following GTP, a positive byoyomi time with no byoyomi stones means there is no limit.
+    pub fn is_unlimited(&self) -> bool {
+        self.byoyomi_time > 0.0 && self.byoyomi_stones == 0
+    }
+}
+
+/// Time remaining on one player's clock.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clock {
+    /// Seconds left in main time, or in the current byoyomi period.
+    pub time: f32,
+    /// Stones left to play in the current byoyomi period, or zero while in main time.
+    pub stones: u32,
+}
+
+/// Tracks the game clock of both players and decides how long to think about each move.
+#[derive(Clone, Debug)]
+pub struct TimeControl {
+    settings: Option<TimeSettings>,
+    clocks: [Clock; 2],
+}
+
+impl TimeControl {
+    /// Create a time control with no limits.
+    pub fn new() -> TimeControl {
+        TimeControl {
+            settings: None,
+            clocks: [Clock { time: 0.0, stones: 0 }; 2],
+        }
+    }
+
+    /// Set the clock settings and reset both clocks to the start of main time.
+    pub fn set_settings(&mut self, settings: TimeSettings) {
+        if settings.is_unlimited() {
+            self.settings = None;
+            return;
+        }
+        self.settings = Some(settings);
+        let clock = if settings.main_time > 0.0 {
+            Clock {
+                time: settings.main_time,
+                stones: 0,
+            }
+        } else {
+            Clock {
+                time: settings.byoyomi_time,
+                stones: settings.byoyomi_stones,
+            }
+        };
+        self.clocks = [clock; 2];
+    }
+
+    pub fn clock(&self, color: Color) -> Clock {
+        self.clocks[color as usize]
+    }
+
+    /// Update a player's clock, as reported by the controller with `time_left`.
+    pub fn set_clock(&mut self, color: Color, clock: Clock) {
+        self.clocks[color as usize] = clock;
+    }
+
+    /// Deduct the time spent on a move from a player's clock, moving into byoyomi or a new
+    /// byoyomi period as needed. The controller's `time_left` reports take precedence over this,
+    /// but it keeps the clock reasonable when they are not sent.
+    pub fn charge(&mut self, color: Color, elapsed: f32) {
+        let settings = match self.settings {
+            Some(settings) => settings,
+            None => return,
+        };
+        let clock = &mut self.clocks[color as usize];
+        clock.time -= elapsed;
+        if clock.stones == 0 {
+            if clock.time <= 0.0 && settings.byoyomi_time > 0.0 {
+                // main time is used up, carry the overrun into the first byoyomi period
+                clock.time += settings.byoyomi_time;
+                clock.stones = settings.byoyomi_stones;
+            }
+        } else {
+            clock.stones -= 1;
+            if clock.stones == 0 {
+                clock.time = settings.byoyomi_time;
+                clock.stones = settings.byoyomi_stones;
+            }
+        }
+        if clock.time < 0.0 {
+            clock.time = 0.0;
+        }
+    }
+
+    /// Decide how many seconds to spend on the next move for `color`, or None if there is no time
+    /// limit. `empty` and `area` are the number of empty cells and the size of the board.
+    ///
+    /// In main time, the remaining time is divided over an estimate of the number of moves left,
+    /// weighted so that the most time is spent in the middlegame. In byoyomi, the period is split
+    /// evenly over its stones.
+    pub fn allocate(&self, color: Color, empty: usize, area: usize) -> Option<f32> {
+        let settings = match self.settings {
+            Some(settings) => settings,
+            None => return None,
+        };
+        let clock = self.clock(color);
+        let budget = if clock.stones > 0 {
+            clock.time / clock.stones as f32
+        } else {
+            let moves_left = (empty as f32 * MOVES_PER_EMPTY_CELL).max(MIN_MOVES_LEFT);
+            let filled = 1.0 - empty as f32 / area as f32;
+            let distance = (filled - MIDDLEGAME_PEAK) / MIDDLEGAME_WIDTH;
+            let weight = 1.0 + MIDDLEGAME_BOOST * (-distance * distance).exp();
+            let mut budget = (clock.time / moves_left * weight).min(clock.time * MAX_MAIN_FRACTION);
+            if settings.byoyomi_time > 0.0 {
+                // main time is not our only time, so we can afford the first byoyomi period too
+                let stones = settings.byoyomi_stones.max(1) as f32;
+                budget = budget.max(settings.byoyomi_time / stones);
+            }
+            budget
+        };
+        Some((budget - SAFETY_MARGIN).max(SAFETY_MARGIN))
+    }
+}
+
+#[test]
+fn test_time_control() {
+    let mut time = TimeControl::new();
+    assert_eq!(time.allocate(Color::Black, 121, 121), None);
+    time.set_settings(TimeSettings {
+        main_time: 10.0,
+        byoyomi_time: 5.0,
+        byoyomi_stones: 2,
+    });
+    // overrunning main time carries the overrun into the first byoyomi period
+    time.charge(Color::Black, 12.0);
+    assert_eq!(time.clock(Color::Black), Clock { time: 3.0, stones: 2 });
+    assert_eq!(time.clock(Color::White), Clock { time: 10.0, stones: 0 });
+    assert_eq!(time.allocate(Color::Black, 100, 121), Some(1.5 - SAFETY_MARGIN));
+    // the period restarts once its stones are played
+    time.charge(Color::Black, 1.0);
+    assert_eq!(time.clock(Color::Black), Clock { time: 2.0, stones: 1 });
+    time.charge(Color::Black, 1.0);
+    assert_eq!(time.clock(Color::Black), Clock { time: 5.0, stones: 2 });
+
+    time.set_settings(TimeSettings {
+        main_time: 1.0,
+        byoyomi_time: 0.0,
+        byoyomi_stones: 0,
+    });
+    time.charge(Color::White, 2.0);
+    assert_eq!(time.clock(Color::White), Clock { time: 0.0, stones: 0 });
+    assert_eq!(time.allocate(Color::White, 100, 121), Some(SAFETY_MARGIN));
+
+    time.set_settings(TimeSettings {
+        main_time: 300.0,
+        byoyomi_time: 0.0,
+        byoyomi_stones: 0,
+    });
+    let opening = time.allocate(Color::Black, 121, 121).unwrap();
+    let middlegame = time.allocate(Color::Black, 97, 121).unwrap();
+    assert!(middlegame > opening);
+
+    let unlimited = TimeSettings {
+        main_time: 300.0,
+        byoyomi_time: 1.0,
+        byoyomi_stones: 0,
+    };
+    assert!(unlimited.is_unlimited());
+    time.set_settings(unlimited);
+    time.charge(Color::Black, 1000.0);
+    assert_eq!(time.allocate(Color::Black, 97, 121), None);
+}
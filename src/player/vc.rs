@@ -0,0 +1,457 @@
+//! Virtual connections, computed with H-search.
+//!
+//! A full connection between two points means that their owner can connect them even if the
+//! opponent moves first, using only the empty cells in its carrier. A semi connection means that
+//! they can be connected if the owner moves first, by playing its key. Connections are built up
+//! from adjacent points with two rules:
+//!
+//! - AND: two full connections `x-z` and `z-y` with disjoint carriers combine into a full
+//!   connection `x-y` if `z` is one of the owner's groups, or into a semi connection with key `z`
+//!   if `z` is empty.
+//! - OR: semi connections between the same points whose carriers have no common cell combine into
+//!   a full connection, since whatever the opponent plays, one of them is still intact.
+//!
+//! The rules are not complete, so some connections are missed, but every connection found is
+//! real.
+
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::hash::BuildHasherDefault;
+use fnv::FnvHasher;
+use super::board::{Board, Color, Move, Pos, Coord};
+use super::misc::bitset::BitSet;
+
+/// How many full connections with different carriers are kept for each pair of points.
+const MAX_FULL: usize = 4;
+
+/// How many semi connections with different carriers are kept for each pair of points.
+const MAX_SEMI: usize = 8;
+
+type FnvHashMap<K, V> = HashMap<K, V, BuildHasherDefault<FnvHasher>>;
+
+/// An endpoint of a virtual connection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Point {
+    /// An empty cell, or any cell of a group.
+    Cell(Pos),
+    /// One of the owner's edges: 0 for the top or left edge, 1 for the bottom or right edge.
+    Edge(usize),
+}
+
+#[derive(Clone, Debug)]
+struct Semi {
+    key: usize,
+    carrier: BitSet,
+}
+
+/// The virtual connections of one player. Points are numbered by cell index for empty cells, by
+/// the smallest cell index in the group for groups, and after the cells for the two edges.
+#[derive(Clone, Debug)]
+pub struct VcSet {
+    color: Color,
+    dims: Pos,
+    /// The point each cell belongs to, or None for the opponent's stones.
+    points: Vec<Option<usize>>,
+    empty: BitSet,
+    /// The points which each point has a full connection with.
+    partners: Vec<Vec<usize>>,
+    full: FnvHashMap<(usize, usize), Vec<BitSet>>,
+    semi: FnvHashMap<(usize, usize), Vec<Semi>>,
+    /// Full connections which have not been combined with the others yet.
+    queue: VecDeque<(usize, usize, BitSet)>,
+}
+
+impl VcSet {
+    /// Compute the connections of `color` on a board.
+    pub fn new(board: &Board, color: Color) -> VcSet {
+        let dims = board.dimensions();
+        let mut vcs = VcSet {
+            color: color,
+            dims: dims,
+            points: Vec::new(),
+            empty: BitSet::new(dims.area()),
+            partners: vec![Vec::new(); dims.area() + 2],
+            full: FnvHashMap::default(),
+            semi: FnvHashMap::default(),
+            queue: VecDeque::new(),
+        };
+        vcs.find_points(board);
+
+        // neighboring points are connected through nothing at all
+        for idx in 0..dims.area() {
+            let a = match vcs.points[idx] {
+                Some(a) => a,
+                None => continue,
+            };
+            for pos in neighbors(vcs.pos_of(idx)) {
+                if let Some(b) = vcs.point_at(board, pos) {
+                    if a != b {
+                        vcs.add_full(a, b, BitSet::new(dims.area()));
+                    }
+                }
+            }
+        }
+        vcs.search();
+        vcs
+    }
+
+    /// Update the connections after `m` has been played on `board`. Connections which the move
+    /// does not break are kept, and only those involving the group the move joins are combined
+    /// again. Swaps change the whole board, so they cause a full recomputation.
+    pub fn play(&mut self, board: &Board, m: Move) {
+        let (color, pos) = match m {
+            Move::Play { color, pos } => (color, pos),
+            Move::Swap { .. } => {
+                *self = VcSet::new(board, self.color);
+                return;
+            }
+            _ => return,
+        };
+        let cell = self.idx_of(pos);
+        let ours = color == self.color;
+        self.find_points(board);
+        let points = self.points.clone();
+        let remap = |x: usize| if x < points.len() { points[x] } else { Some(x) };
+
+        let full = mem::replace(&mut self.full, FnvHashMap::default());
+        let semi = mem::replace(&mut self.semi, FnvHashMap::default());
+        for partners in &mut self.partners {
+            partners.clear();
+        }
+        // our own stones never hurt our connections, but the opponent's break those through them
+        for ((a, b), carriers) in full {
+            if let (Some(a), Some(b)) = (remap(a), remap(b)) {
+                for mut carrier in carriers {
+                    if ours {
+                        carrier.remove(cell);
+                    } else if carrier.contains(cell) {
+                        continue;
+                    }
+                    if a != b {
+                        self.insert_full(a, b, carrier);
+                    }
+                }
+            }
+        }
+        for ((a, b), semis) in semi {
+            if let (Some(a), Some(b)) = (remap(a), remap(b)) {
+                for mut s in semis {
+                    if ours {
+                        s.carrier.remove(cell);
+                    } else if s.carrier.contains(cell) {
+                        continue;
+                    }
+                    if a == b {
+                        continue;
+                    } else if ours && s.key == cell {
+                        // we played the key, so the connection is now complete
+                        self.add_full(a, b, s.carrier);
+                    } else {
+                        self.add_semi(a, b, s.key, s.carrier);
+                    }
+                }
+            }
+        }
+
+        // the move may have merged groups, so connections of the new group are combined again
+        if ours {
+            let group = self.points[cell].unwrap();
+            for w in self.partners[group].clone() {
+                for carrier in self.full[&key(group, w)].clone() {
+                    self.queue.push_back((group, w, carrier));
+                }
+            }
+            let semis: Vec<_> = self.semi
+                                    .iter()
+                                    .filter(|&(&(a, b), _)| a == group || b == group)
+                                    .flat_map(|(&(a, b), semis)| {
+                                        semis.iter().map(move |s| (a, b, s.carrier.clone()))
+                                    })
+                                    .collect();
+            for (a, b, carrier) in semis {
+                self.try_or(a, b, &carrier);
+            }
+        }
+        self.search();
+    }
+
+    /// All full connections between two points, smallest carrier first.
+    pub fn full_connections(&self, a: Point, b: Point) -> Vec<Vec<Pos>> {
+        let carriers = match (self.id(a), self.id(b)) {
+            (Some(a), Some(b)) => self.full.get(&key(a, b)).cloned().unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        self.sorted_carriers(carriers)
+    }
+
+    /// All semi connections between two points, smallest carrier first. Each carrier includes the
+    /// key of the connection.
+    pub fn semi_connections(&self, a: Point, b: Point) -> Vec<Vec<Pos>> {
+        let carriers = match (self.id(a), self.id(b)) {
+            (Some(a), Some(b)) => {
+                self.semi
+                    .get(&key(a, b))
+                    .map_or(Vec::new(), |semis| semis.iter().map(|s| s.carrier.clone()).collect())
+            }
+            _ => Vec::new(),
+        };
+        self.sorted_carriers(carriers)
+    }
+
+    /// The carrier of the smallest full connection between two points, if they are connected.
+    pub fn connection(&self, a: Point, b: Point) -> Option<Vec<Pos>> {
+        self.full_connections(a, b).into_iter().next()
+    }
+
+    /// The carrier of a full connection between the two edges, if the player has already won.
+    pub fn winning_connection(&self) -> Option<Vec<Pos>> {
+        self.connection(Point::Edge(0), Point::Edge(1))
+    }
+
+    /// Combine queued connections until no new connections are found.
+    fn search(&mut self) {
+        while let Some((x, y, carrier)) = self.queue.pop_front() {
+            // the connection may have been replaced by a smaller one in the meantime
+            if !self.full.get(&key(x, y)).map_or(false, |cs| cs.contains(&carrier)) {
+                continue;
+            }
+            for &(mid, other) in &[(x, y), (y, x)] {
+                let mut found = Vec::new();
+                for &w in &self.partners[mid] {
+                    if w == other {
+                        continue;
+                    }
+                    for c in &self.full[&key(mid, w)] {
+                        if c.is_disjoint(&carrier) && !c.contains(other) && !carrier.contains(w) {
+                            let mut union = c.clone();
+                            union.union_with(&carrier);
+                            found.push((w, union));
+                        }
+                    }
+                }
+                for (w, mut union) in found {
+                    if self.is_group(mid) {
+                        self.add_full(other, w, union);
+                    } else {
+                        union.insert(mid);
+                        self.add_semi(other, w, mid, union);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add a full connection without queueing it, unless a smaller one is already known.
+    fn insert_full(&mut self, a: usize, b: usize, carrier: BitSet) -> bool {
+        let k = key(a, b);
+        {
+            let carriers = self.full.entry(k).or_insert_with(Vec::new);
+            if carriers.iter().any(|c| c.is_subset(&carrier)) {
+                return false;
+            }
+            carriers.retain(|c| !carrier.is_subset(c));
+            if carriers.len() >= MAX_FULL {
+                return false;
+            }
+            carriers.push(carrier.clone());
+        }
+        if !self.partners[a].contains(&b) {
+            self.partners[a].push(b);
+            self.partners[b].push(a);
+        }
+        // semi connections which need at least as many cells are now useless
+        if let Some(semis) = self.semi.get_mut(&k) {
+            semis.retain(|s| !carrier.is_subset(&s.carrier));
+        }
+        true
+    }
+
+    fn add_full(&mut self, a: usize, b: usize, carrier: BitSet) {
+        if self.insert_full(a, b, carrier.clone()) {
+            let (a, b) = key(a, b);
+            self.queue.push_back((a, b, carrier));
+        }
+    }
+
+    fn add_semi(&mut self, a: usize, b: usize, key_cell: usize, carrier: BitSet) {
+        let k = key(a, b);
+        if self.full.get(&k).map_or(false, |cs| cs.iter().any(|c| c.is_subset(&carrier))) {
+            return;
+        }
+        {
+            let semis = self.semi.entry(k).or_insert_with(Vec::new);
+            if semis.iter().any(|s| s.carrier.is_subset(&carrier)) {
+                return;
+            }
+            semis.retain(|s| !carrier.is_subset(&s.carrier));
+            if semis.len() >= MAX_SEMI {
+                return;
+            }
+            semis.push(Semi {
+                key: key_cell,
+                carrier: carrier.clone(),
+            });
+        }
+        self.try_or(k.0, k.1, &carrier);
+    }
+
+    /// Apply the OR rule, looking for semi connections which together with `carrier` leave the
+    /// opponent no cell that breaks them all.
+    fn try_or(&mut self, a: usize, b: usize, carrier: &BitSet) {
+        let union = {
+            let mut semis: Vec<&BitSet> = match self.semi.get(&key(a, b)) {
+                Some(semis) => semis.iter().map(|s| &s.carrier).collect(),
+                None => return,
+            };
+            semis.sort_by_key(|c| c.len());
+            let mut union = carrier.clone();
+            let mut intersection = carrier.clone();
+            let mut size = intersection.len();
+            for c in semis {
+                let mut smaller = intersection.clone();
+                smaller.intersect_with(c);
+                let smaller_size = smaller.len();
+                if smaller_size < size {
+                    intersection = smaller;
+                    size = smaller_size;
+                    union.union_with(c);
+                    if size == 0 {
+                        break;
+                    }
+                }
+            }
+            if size > 0 {
+                return;
+            }
+            union
+        };
+        self.add_full(a, b, union);
+    }
+
+    /// Work out which point each cell belongs to, grouping connected stones of our color.
+    fn find_points(&mut self, board: &Board) {
+        let area = self.dims.area();
+        self.points = vec![None; area];
+        self.empty = BitSet::new(area);
+        for idx in 0..area {
+            let pos = self.pos_of(idx);
+            match board.get(pos) {
+                None => {
+                    self.points[idx] = Some(idx);
+                    self.empty.insert(idx);
+                }
+                Some(c) if c == self.color && self.points[idx].is_none() => {
+                    // flood fill the group, naming it after its first cell
+                    let mut stack = vec![pos];
+                    self.points[idx] = Some(idx);
+                    while let Some(p) = stack.pop() {
+                        for n in neighbors(p) {
+                            if board.on_board(n) && board.get(n) == Some(self.color) {
+                                let i = self.idx_of(n);
+                                if self.points[i].is_none() {
+                                    self.points[i] = Some(idx);
+                                    stack.push(n);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// The point at a position, which may be off the board on one of our edges.
+    fn point_at(&self, board: &Board, pos: Pos) -> Option<usize> {
+        if board.on_board(pos) {
+            self.points[self.idx_of(pos)]
+        } else if board.get(pos) == Some(self.color) {
+            let first = match self.color {
+                Color::Black => pos.y < 0,
+                Color::White => pos.x < 0,
+            };
+            Some(self.dims.area() + if first { 0 } else { 1 })
+        } else {
+            None
+        }
+    }
+
+    fn id(&self, p: Point) -> Option<usize> {
+        match p {
+            Point::Cell(pos) => {
+                if pos.x >= 0 && pos.y >= 0 && pos.x < self.dims.x && pos.y < self.dims.y {
+                    self.points[self.idx_of(pos)]
+                } else {
+                    None
+                }
+            }
+            Point::Edge(edge) if edge < 2 => Some(self.dims.area() + edge),
+            Point::Edge(_) => None,
+        }
+    }
+
+    /// Whether a point is one of our groups or edges, rather than an empty cell.
+    fn is_group(&self, point: usize) -> bool {
+        !self.empty.contains(point)
+    }
+
+    fn sorted_carriers(&self, mut carriers: Vec<BitSet>) -> Vec<Vec<Pos>> {
+        carriers.sort_by_key(|c| c.len());
+        carriers.iter().map(|c| c.iter().map(|i| self.pos_of(i)).collect()).collect()
+    }
+
+    fn idx_of(&self, pos: Pos) -> usize {
+        pos.y as usize * self.dims.x as usize + pos.x as usize
+    }
+
+    fn pos_of(&self, idx: usize) -> Pos {
+        Pos::new((idx % self.dims.x as usize) as Coord,
+                 (idx / self.dims.x as usize) as Coord)
+    }
+}
+
+fn key(a: usize, b: usize) -> (usize, usize) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+fn neighbors(pos: Pos) -> Vec<Pos> {
+    [(-1, 0), (0, -1), (-1, 1), (0, 1), (1, 0), (1, -1)]
+        .iter()
+        .map(|&d| pos + d.into())
+        .collect()
+}
+
+#[test]
+fn test_vc_templates() {
+    // a stone in the middle of a 3x3 board reaches both edges with bridge templates
+    let mut board = Board::new((3, 3));
+    board.play(Move::new(Color::Black, (1, 1)));
+    let black = VcSet::new(&board, Color::Black);
+    let mut carrier = black.winning_connection().unwrap();
+    carrier.sort_by_key(|p| (p.y, p.x));
+    assert_eq!(carrier,
+               vec![Pos::new(1, 0), Pos::new(2, 0), Pos::new(0, 2), Pos::new(1, 2)]);
+    assert!(VcSet::new(&board, Color::White).winning_connection().is_none());
+
+    // edge template IIIa from the third row, with the opponent nowhere near
+    let mut board = Board::new((5, 5));
+    board.play(Move::new(Color::Black, (2, 2)));
+    let black = VcSet::new(&board, Color::Black);
+    let stone = Point::Cell(Pos::new(2, 2));
+    assert!(black.connection(stone, Point::Edge(0)).is_some());
+    assert!(black.connection(stone, Point::Edge(1)).is_some());
+
+    // after an intrusion, updating incrementally agrees with starting over
+    let mut incremental = black.clone();
+    for &(color, pos) in &[(Color::White, (2, 3)), (Color::Black, (1, 3)), (Color::White, (1, 4))] {
+        let m = Move::new(color, pos);
+        board.play(m);
+        incremental.play(&board, m);
+        let fresh = VcSet::new(&board, Color::Black);
+        for &edge in &[0, 1] {
+            assert_eq!(incremental.connection(stone, Point::Edge(edge)).is_some(),
+                       fresh.connection(stone, Point::Edge(edge)).is_some());
+        }
+    }
+}
+
@@ -0,0 +1,750 @@
+//! Analysis of tournament results: win rates broken down by opening and color, openings stolen by
+//! one program, differences between two tournaments, and estimates of the Elo difference between
+//! the programs.
+//!
+//! Games count only if both programs agree on the winner. The first program is the one which
+//! plays Black in the first such game, which is how tournaments are scheduled.
+
+use std::collections::BTreeMap;
+use std::f64;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use player::board::Color;
+use super::{load_results, GameResult};
+
+pub const USAGE: &'static str = "usage: coronene summary <results> [options]\n\
+                                 options:\n    \
+                                 --count <n>          only count the first n games\n    \
+                                 --long               tell openings apart by all of their \
+                                 moves\n    \
+                                 --openings <file>    only count openings listed in the file\n    \
+                                 --time <seconds>     report wins which took longer than this\n    \
+                                 --table              show the results of each opening\n    \
+                                 --steals             list openings won with both colors by one \
+                                 program\n    \
+                                 --compare <results>  list games whose result differs from \
+                                 another tournament\n    \
+                                 --sprt <elo0>,<elo1>[,<alpha>,<beta>]\n    \
+                                 \x20                    test whether p1 is elo0 or elo1 stronger";
+
+/// Simple statistics of a series of values.
+#[derive(Clone, Debug)]
+pub struct Statistics {
+    count: usize,
+    sum: f64,
+    sum_sq: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Statistics {
+    pub fn new() -> Statistics {
+        Statistics {
+            count: 0,
+            sum: 0.0,
+            sum_sq: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_sq += value * value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    pub fn stddev(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        (self.sum_sq / self.count as f64 - mean * mean).max(0.0).sqrt()
+    }
+
+    /// The standard error of the mean.
+    pub fn stderror(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.stddev() / (self.count as f64).sqrt()
+        }
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{:.1} (+-{:.1}) min={:.1} max={:.1} deviation={:.1}",
+               self.mean(),
+               self.stderror(),
+               if self.count == 0 { 0.0 } else { self.min },
+               if self.count == 0 { 0.0 } else { self.max },
+               self.stddev())
+    }
+}
+
+/// The error function, to within 1.5e-7 (Abramowitz and Stegun 7.1.26).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t *
+               (0.254829592 +
+                t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 { -y } else { y }
+}
+
+/// The Elo difference at which a player is expected to score `score`.
+fn elo_of_score(score: f64) -> f64 {
+    if score <= 0.0 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        f64::INFINITY
+    } else {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+/// The expected score of a player who is `elo` points stronger.
+fn score_of_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// An estimate of how much stronger a program is, from its wins and losses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Elo {
+    pub wins: usize,
+    pub losses: usize,
+    pub elo: f64,
+    /// The bounds of a 95% confidence interval.
+    pub lower: f64,
+    pub upper: f64,
+    /// The likelihood of superiority: the probability that the program really is stronger.
+    pub los: f64,
+}
+
+impl Elo {
+    pub fn new(wins: usize, losses: usize) -> Elo {
+        let (w, l) = (wins as f64, losses as f64);
+        let n = w + l;
+        if n == 0.0 {
+            return Elo {
+                wins: 0,
+                losses: 0,
+                elo: 0.0,
+                lower: f64::NEG_INFINITY,
+                upper: f64::INFINITY,
+                los: 0.5,
+            };
+        }
+        let score = w / n;
+        let margin = 1.96 * (score * (1.0 - score) / n).sqrt();
+        Elo {
+            wins: wins,
+            losses: losses,
+            elo: elo_of_score(score),
+            lower: elo_of_score(score - margin),
+            upper: elo_of_score(score + margin),
+            los: 0.5 * (1.0 + erf((w - l) / (2.0 * n).sqrt())),
+        }
+    }
+}
+
+impl fmt::Display for Elo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{:+.1} (95%: {:+.1} to {:+.1}), LOS {:.1}%",
+               self.elo,
+               self.lower,
+               self.upper,
+               self.los * 100.0)
+    }
+}
+
+/// A sequential probability ratio test of whether a program is `elo1` points stronger rather than
+/// `elo0`, with false positive and false negative rates `alpha` and `beta`. It can be checked
+/// after every game, and stops the match once the result is significant either way.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SprtResult {
+    /// The program is no more than `elo0` points stronger.
+    AcceptH0,
+    /// The program is at least `elo1` points stronger.
+    AcceptH1,
+    /// More games are needed.
+    Continue,
+}
+
+impl Sprt {
+    /// Parse `elo0,elo1` or `elo0,elo1,alpha,beta`. The error rates default to 0.05.
+    pub fn parse(s: &str) -> Result<Sprt, String> {
+        let err = || format!("invalid SPRT (expected elo0,elo1[,alpha,beta]): {}", s);
+        let values: Vec<f64> = try!(s.split(',')
+                                     .map(|v| v.trim().parse().map_err(|_| err()))
+                                     .collect());
+        let (alpha, beta) = match values.len() {
+            2 => (0.05, 0.05),
+            4 => (values[2], values[3]),
+            _ => return Err(err()),
+        };
+        if values[0] >= values[1] || alpha <= 0.0 || alpha >= 1.0 || beta <= 0.0 || beta >= 1.0 {
+            return Err(err());
+        }
+        Ok(Sprt {
+            elo0: values[0],
+            elo1: values[1],
+            alpha: alpha,
+            beta: beta,
+        })
+    }
+
+    /// The log likelihood ratio of the two hypotheses after some wins and losses.
+    pub fn llr(&self, wins: usize, losses: usize) -> f64 {
+        let (p0, p1) = (score_of_elo(self.elo0), score_of_elo(self.elo1));
+        wins as f64 * (p1 / p0).ln() + losses as f64 * ((1.0 - p1) / (1.0 - p0)).ln()
+    }
+
+    /// The bounds on the log likelihood ratio at which each hypothesis is accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn test(&self, wins: usize, losses: usize) -> SprtResult {
+        let llr = self.llr(wins, losses);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+
+    /// A line describing the state of the test.
+    pub fn report(&self, wins: usize, losses: usize) -> String {
+        let (lower, upper) = self.bounds();
+        let verdict = match self.test(wins, losses) {
+            SprtResult::AcceptH0 => format!("accept elo <= {}", self.elo0),
+            SprtResult::AcceptH1 => format!("accept elo >= {}", self.elo1),
+            SprtResult::Continue => "continue".into(),
+        };
+        format!("SPRT elo0={} elo1={} alpha={} beta={}: LLR {:.2} ({:.2}, {:.2}), {}",
+                self.elo0,
+                self.elo1,
+                self.alpha,
+                self.beta,
+                self.llr(wins, losses),
+                lower,
+                upper,
+                verdict)
+    }
+}
+
+/// The wins and losses of `name` in the games whose winner is agreed on.
+pub fn score(games: &[GameResult], name: &str) -> (usize, usize) {
+    let winners: Vec<&str> = games.iter().filter_map(|g| g.winner_name()).collect();
+    let wins = winners.iter().filter(|&&w| w == name).count();
+    (wins, winners.len() - wins)
+}
+
+/// Which games to summarize.
+#[derive(Clone, Debug)]
+pub struct SummaryOptions {
+    /// Only the first this many games with a result are counted.
+    pub max_games: usize,
+    /// Whether openings are told apart by all of their moves, rather than just the first.
+    pub long_openings: bool,
+    /// Wins using more than this many seconds are reported as timeouts.
+    pub time_limit: f64,
+    /// If not empty, only games from these openings are counted.
+    pub openings: Vec<String>,
+}
+
+impl SummaryOptions {
+    pub fn new() -> SummaryOptions {
+        SummaryOptions {
+            max_games: usize::max_value(),
+            long_openings: false,
+            time_limit: f64::INFINITY,
+            openings: Vec::new(),
+        }
+    }
+}
+
+/// Results of one opening, indexed by program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpeningResults {
+    pub games: usize,
+    pub black_wins: [usize; 2],
+    pub black_losses: [usize; 2],
+}
+
+#[derive(Clone, Debug)]
+pub struct Summary {
+    /// The first and second program.
+    pub programs: Vec<String>,
+    /// Games which were left out because there was no agreed result.
+    pub ignored: usize,
+    /// Games which were left out because the maximum number of games was reached.
+    pub skipped: usize,
+    pub length: Statistics,
+    /// Seconds used in each game by each program.
+    pub time: [Statistics; 2],
+    /// One for each game won by the first program, and zero for each lost, overall and by the
+    /// color it played.
+    pub wins: Statistics,
+    pub wins_black: Statistics,
+    pub wins_white: Statistics,
+    /// How far over the time limit each program was in the games it won over it.
+    pub overtime: [Statistics; 2],
+    pub openings: BTreeMap<String, OpeningResults>,
+}
+
+impl Summary {
+    pub fn new(games: &[GameResult], options: &SummaryOptions) -> Summary {
+        let mut summary = Summary {
+            programs: Vec::new(),
+            ignored: 0,
+            skipped: 0,
+            length: Statistics::new(),
+            time: [Statistics::new(), Statistics::new()],
+            wins: Statistics::new(),
+            wins_black: Statistics::new(),
+            wins_white: Statistics::new(),
+            overtime: [Statistics::new(), Statistics::new()],
+            openings: BTreeMap::new(),
+        };
+        for game in games {
+            let opening = if options.long_openings {
+                game.opening.trim()
+            } else {
+                game.opening.split_whitespace().next().unwrap_or("")
+            };
+            if !options.openings.is_empty() && !options.openings.iter().any(|o| o == opening) {
+                continue;
+            }
+            if summary.wins.count() >= options.max_games {
+                summary.skipped += 1;
+                continue;
+            }
+            let winner = match game.winner() {
+                Some(winner) => winner,
+                None => {
+                    summary.ignored += 1;
+                    continue;
+                }
+            };
+            for name in &[&game.black, &game.white] {
+                if !summary.programs.contains(name) {
+                    summary.programs.push((*name).clone());
+                }
+            }
+            let black = summary.programs.iter().position(|p| *p == game.black).unwrap();
+            let white = summary.programs.iter().position(|p| *p == game.white).unwrap();
+            let (winner_idx, winner_time) = match winner {
+                Color::Black => (black, game.time_black),
+                Color::White => (white, game.time_white),
+            };
+            if winner_time > options.time_limit {
+                summary.overtime[winner_idx.min(1)].add(winner_time - options.time_limit);
+            }
+
+            let value = (winner_idx == 0) as u8 as f64;
+            summary.length.add(game.length as f64);
+            summary.wins.add(value);
+            if black == 0 {
+                summary.wins_black.add(value);
+                summary.time[0].add(game.time_black);
+                summary.time[1].add(game.time_white);
+            } else {
+                summary.wins_white.add(value);
+                summary.time[0].add(game.time_white);
+                summary.time[1].add(game.time_black);
+            }
+
+            let results = summary.openings.entry(opening.into()).or_insert_with(Default::default);
+            results.games += 1;
+            if winner == Color::Black {
+                results.black_wins[black.min(1)] += 1;
+            } else {
+                results.black_losses[black.min(1)] += 1;
+            }
+        }
+        summary
+    }
+
+    pub fn elo(&self) -> Elo {
+        let wins = self.wins.sum() as usize;
+        Elo::new(wins, self.wins.count() - wins)
+    }
+
+    /// A table of the wins and losses of each program as Black in each opening.
+    pub fn table(&self) -> String {
+        let mut out = String::from("+-------------+--------+-------+-------+\n\
+                                    +   OPENING   | COUNT  |  p1   |  p2   |\n\
+                                    +-------------+--------+-------+-------+\n");
+        let mut total = OpeningResults::default();
+        for (opening, results) in &self.openings {
+            out.push_str(&format!("|{:<13}|{:>7} |{:>4}/{:<2}|{:>4}/{:<2}|\n",
+                                  opening,
+                                  results.games,
+                                  results.black_wins[0],
+                                  results.black_losses[0],
+                                  results.black_wins[1],
+                                  results.black_losses[1]));
+            for i in 0..2 {
+                total.black_wins[i] += results.black_wins[i];
+                total.black_losses[i] += results.black_losses[i];
+            }
+        }
+        out.push_str(&format!("+--------------------------------------+\n\
+                               |{:>22}|{:>4}/{:<2}|{:>4}/{:<2}|\n\
+                               +--------------------------------------+",
+                              "",
+                              total.black_wins[0],
+                              total.black_losses[0],
+                              total.black_wins[1],
+                              total.black_losses[1]));
+        out
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.wins.count() == 0 {
+            return write!(f, "No valid games.");
+        }
+        let rule = "===========================================================";
+        let thin = "-----------------------------------------------------------";
+        let percent = |stats: &Statistics| {
+            format!("{:.1}% (+-{:.1})", stats.mean() * 100.0, stats.stderror() * 100.0)
+        };
+        try!(writeln!(f, "{}", rule));
+        try!(writeln!(f, "  NumGames: {}", self.wins.count()));
+        try!(writeln!(f, "   GameLen: {}", self.length));
+        try!(writeln!(f, "    p1Time: {}", self.time[0]));
+        try!(writeln!(f, "    p2Time: {}", self.time[1]));
+        try!(writeln!(f, "{}", thin));
+        try!(writeln!(f, "Statistics for {}:", self.programs[0]));
+        try!(writeln!(f, "  All Wins: {}", percent(&self.wins)));
+        try!(writeln!(f, "  As Black: {}", percent(&self.wins_black)));
+        try!(writeln!(f, "  As White: {}", percent(&self.wins_white)));
+        try!(writeln!(f, "       Elo: {}", self.elo()));
+        if self.overtime.iter().any(|o| o.count() > 0) {
+            try!(writeln!(f, "{}", thin));
+            let wins = self.wins.sum() as usize;
+            for (i, &won) in [wins, self.wins.count() - wins].iter().enumerate() {
+                let overtime = &self.overtime[i];
+                try!(writeln!(f,
+                              "Timeouts for {}: {}/{}, {:.1} (+-{:.1}) max={:.1}",
+                              self.programs.get(i).map_or("?", |p| &p[..]),
+                              overtime.count(),
+                              won,
+                              overtime.mean(),
+                              overtime.stderror(),
+                              if overtime.count() == 0 { 0.0 } else { overtime.max() }));
+            }
+        }
+        write!(f, "{}", rule)
+    }
+}
+
+/// Openings won with both colors by the same program, found by pairing consecutive games.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Steals {
+    pub pairs: usize,
+    /// The indexes of the two games of each stolen opening, and who stole it.
+    pub steals: Vec<(usize, usize, String)>,
+}
+
+impl Steals {
+    pub fn new(games: &[GameResult]) -> Result<Steals, String> {
+        let mut steals = Steals {
+            pairs: 0,
+            steals: Vec::new(),
+        };
+        for pair in games.chunks(2).filter(|pair| pair.len() == 2) {
+            let (first, second) = (&pair[0], &pair[1]);
+            if first.opening != second.opening {
+                return Err(format!("openings mismatch in games {} and {}",
+                                   first.index,
+                                   second.index));
+            }
+            let (a, b) = match (first.winner_name(), second.winner_name()) {
+                (Some(a), Some(b)) => (a, b),
+                _ => {
+                    return Err(format!("results mismatch in games {} and {}",
+                                       first.index,
+                                       second.index))
+                }
+            };
+            steals.pairs += 1;
+            if a == b {
+                steals.steals.push((first.index, second.index, a.into()));
+            }
+        }
+        Ok(steals)
+    }
+
+    /// How many openings a program stole.
+    pub fn count(&self, name: &str) -> usize {
+        self.steals.iter().filter(|s| s.2 == name).count()
+    }
+}
+
+/// The games whose result changed between two tournaments with the same schedule, such as one
+/// between new versions of the programs. Since the programs may have different names, they are
+/// told apart by position: in even games the first program plays Black.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Differences {
+    /// The games which the first program won in the second tournament but not the first, and
+    /// those which it lost.
+    pub gains: [Vec<usize>; 2],
+    /// Games in which the programs disagree about the result.
+    pub disagreements: Vec<usize>,
+}
+
+impl Differences {
+    pub fn new(old: &[GameResult], new: &[GameResult]) -> Result<Differences, String> {
+        let mut differences = Differences {
+            gains: [Vec::new(), Vec::new()],
+            disagreements: Vec::new(),
+        };
+        for (a, b) in old.iter().zip(new) {
+            if a.index != b.index {
+                return Err(format!("games do not align: {} and {}", a.index, b.index));
+            }
+            if a.opening != b.opening {
+                return Err(format!("openings mismatch in game {}", a.index));
+            }
+            match (a.winner(), b.winner()) {
+                (Some(old), Some(new)) if old != new => {
+                    let first_black = b.index % 2 == 0;
+                    let first_won = (new == Color::Black) == first_black;
+                    differences.gains[if first_won { 0 } else { 1 }].push(b.index);
+                }
+                (Some(_), Some(_)) => (),
+                _ => differences.disagreements.push(a.index),
+            }
+        }
+        Ok(differences)
+    }
+}
+
+/// Print the analyses asked for by the arguments which follow `coronene summary`.
+pub fn run<I>(mut args: I) -> Result<(), String>
+    where I: Iterator<Item = String>
+{
+    fn parse<T: ::std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+        value.parse().map_err(|_| format!("invalid value for {}: {}", name, value))
+    }
+    let path = try!(args.next().ok_or("missing results file"));
+    let mut options = SummaryOptions::new();
+    let (mut table, mut steals, mut compare, mut sprt) = (false, false, None, None);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--long" => options.long_openings = true,
+            "--table" => table = true,
+            "--steals" => steals = true,
+            _ => {
+                let value = try!(args.next().ok_or(format!("missing value for {}", arg)));
+                match &arg[..] {
+                    "--count" => options.max_games = try!(parse(&arg, &value)),
+                    "--time" => options.time_limit = try!(parse(&arg, &value)),
+                    "--openings" => {
+                        let mut text = String::new();
+                        try!(File::open(&value)
+                                 .and_then(|mut f| f.read_to_string(&mut text))
+                                 .map_err(|e| format!("cannot read {}: {}", value, e)));
+                        options.openings = text.lines()
+                                               .map(|l| l.trim().to_string())
+                                               .filter(|l| !l.is_empty())
+                                               .collect();
+                    }
+                    "--compare" => compare = Some(value),
+                    "--sprt" => sprt = Some(try!(Sprt::parse(&value))),
+                    _ => return Err(format!("unknown option: {}", arg)),
+                }
+            }
+        }
+    }
+
+    println!("Analyzing: '{}'...", path);
+    let games = try!(load_results(&path));
+    let summary = Summary::new(&games, &options);
+    if summary.ignored > 0 {
+        println!("Ignored {} games without a result", summary.ignored);
+    }
+    if summary.skipped > 0 {
+        println!("Skipped {} games after the first {}", summary.skipped, options.max_games);
+    }
+    for (i, name) in summary.programs.iter().enumerate() {
+        println!("p{} = {}", i + 1, name);
+    }
+    println!("");
+    if table {
+        println!("{}", summary.table());
+    }
+    println!("{}", summary);
+    if let (Some(sprt), true) = (sprt, summary.wins.count() > 0) {
+        // test the games counted in the summary, so that --count and --openings apply
+        let elo = summary.elo();
+        println!("{}", sprt.report(elo.wins, elo.losses));
+    }
+
+    if steals && summary.programs.len() == 2 {
+        let steals = try!(Steals::new(&games));
+        println!("\nStolen games:");
+        for &(a, b, ref name) in &steals.steals {
+            println!("Games {}, {} - {}", a, b, name);
+        }
+        let percent = |n: usize| 100.0 * n as f64 / steals.pairs.max(1) as f64;
+        println!("Number of pairs: {}", steals.pairs);
+        println!("Number of steals: {} ({:.0}%)",
+                 steals.steals.len(),
+                 percent(steals.steals.len()));
+        for (i, name) in summary.programs.iter().enumerate() {
+            let count = steals.count(name);
+            println!("Player {} steals: {} ({:.0}%)", i + 1, count, percent(count));
+        }
+    }
+
+    if let Some(other) = compare {
+        println!("\nDistinguishing: {} and {}", path, other);
+        let differences = try!(Differences::new(&games, &try!(load_results(&other))));
+        for &game in &differences.disagreements {
+            println!("Players disagree on result in game {}!", game);
+        }
+        let mut changed: Vec<(usize, usize)> = (0..2)
+                                                   .flat_map(|i| {
+                                                       differences.gains[i]
+                                                           .iter()
+                                                           .map(move |&game| (game, i))
+                                                   })
+                                                   .collect();
+        changed.sort();
+        for (game, i) in changed {
+            println!("Game {}: P{} gain", game, i + 1);
+        }
+        println!("Total different outcomes: {}",
+                 differences.gains[0].len() + differences.gains[1].len());
+        println!("New Player 1 wins: {}", differences.gains[0].len());
+        println!("New Player 2 wins: {}", differences.gains[1].len());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_analysis() {
+    let game = |index: usize, black: &str, white: &str, result: &str| {
+        GameResult {
+            index: index,
+            round: 0,
+            opening: if index < 2 { "a1 b2".into() } else { "c3".into() },
+            black: black.into(),
+            white: white.into(),
+            result_black: result.into(),
+            result_white: result.into(),
+            length: 20 + index,
+            time_black: 10.0,
+            time_white: 20.0,
+            error: None,
+        }
+    };
+    let games = vec![game(0, "new", "old", "B+"),
+                     game(1, "old", "new", "W+"),
+                     game(2, "new", "old", "W+"),
+                     game(3, "old", "new", "B+"),
+                     game(4, "new", "old", "?")];
+
+    let summary = Summary::new(&games, &SummaryOptions::new());
+    assert_eq!(summary.programs, vec!["new", "old"]);
+    assert_eq!(summary.ignored, 1);
+    assert_eq!(summary.wins.count(), 4);
+    assert_eq!(summary.wins.mean(), 0.5);
+    assert_eq!(summary.wins_black.mean(), 0.5);
+    assert_eq!(summary.time[1].mean(), 15.0);
+    assert_eq!(summary.openings["a1"],
+               OpeningResults {
+                   games: 2,
+                   black_wins: [1, 0],
+                   black_losses: [0, 1],
+               });
+    let mut options = SummaryOptions::new();
+    options.long_openings = true;
+    options.openings = vec!["c3".into()];
+    options.time_limit = 15.0;
+    let summary = Summary::new(&games, &options);
+    assert_eq!(summary.wins.count(), 2);
+    assert_eq!(summary.overtime[1].count(), 1);
+    assert!(summary.to_string().contains("Timeouts for old: 1/2"));
+
+    let mut options = SummaryOptions::new();
+    options.max_games = 2;
+    let summary = Summary::new(&games, &options);
+    assert_eq!(summary.wins.count(), 2);
+    assert_eq!((summary.ignored, summary.skipped), (0, 3));
+    assert_eq!(score(&games, "new"), (2, 2));
+
+    let steals = Steals::new(&games[..4]).unwrap();
+    assert_eq!(steals.pairs, 2);
+    assert_eq!(steals.steals, vec![(0, 1, "new".into()), (2, 3, "old".into())]);
+    assert!(Steals::new(&games[1..3]).is_err());
+
+    let mut changed = games.clone();
+    changed[1].result_black = "B+".into();
+    changed[1].result_white = "B+".into();
+    changed[3].result_white = "W+".into();
+    let differences = Differences::new(&games, &changed).unwrap();
+    assert_eq!(differences.gains, [vec![], vec![1]]);
+    assert_eq!(differences.disagreements, vec![3, 4]);
+
+    // an even score is no difference, and winning three games in four is about +191
+    let even = Elo::new(50, 50);
+    assert_eq!(even.elo, 0.0);
+    assert!((even.los - 0.5).abs() < 1e-6);
+    assert!(even.lower < -60.0 && even.upper > 60.0);
+    let strong = Elo::new(75, 25);
+    assert!((strong.elo - 190.85).abs() < 0.01);
+    assert!(strong.los > 0.999);
+    assert!((erf(1.0) - 0.8427008).abs() < 1e-6);
+
+    let sprt = Sprt::parse("0,50").unwrap();
+    assert_eq!(sprt.alpha, 0.05);
+    assert_eq!(sprt.test(10, 10), SprtResult::Continue);
+    assert_eq!(sprt.test(150, 50), SprtResult::AcceptH1);
+    assert_eq!(sprt.test(40, 70), SprtResult::AcceptH0);
+    assert!(Sprt::parse("10,0").is_err());
+    assert!(Sprt::parse("0,10,0.05").is_err());
+}
@@ -0,0 +1,674 @@
+//! Tournaments between two HTP programs, as `coronene tournament`.
+//!
+//! Each game starts from an opening, a few moves which are played for both programs, after which
+//! they take turns generating moves until the game is won or one of them resigns. Every game is
+//! appended to a `results` file in the tournament directory and saved as an SGF file next to it.
+//! Running the same tournament again carries on after the last game in the results file.
+//!
+//! In an iterative tournament each round plays every opening twice, so that each program plays it
+//! as Black once. In a random tournament each round is a single game from an opening picked at
+//! random, weighted by the number before its moves, and the programs take turns playing Black.
+
+pub mod analysis;
+pub mod program;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use rand::{self, Rng};
+use time;
+use player::board::{Board, Color, Coord, Move, SwapKind};
+use player::sgf::{Game, Property};
+use self::analysis::{Sprt, SprtResult};
+use self::program::{HtpError, Program};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Kind {
+    Iterative,
+    Random,
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentConfig {
+    pub p1name: String,
+    pub p1cmd: String,
+    pub p2name: String,
+    pub p2cmd: String,
+    pub size: Coord,
+    pub rounds: usize,
+    /// The directory for the results file, the game records and the programs' logs.
+    pub dir: String,
+    /// A file with one opening on each line.
+    pub openings: String,
+    pub kind: Kind,
+    /// Whether to show every command and answer.
+    pub verbose: bool,
+    /// A test which stops the tournament once it is clear whether the first program is stronger.
+    pub sprt: Option<Sprt>,
+}
+
+pub const USAGE: &'static str = "usage: coronene tournament [options]\n\
+                                 options:\n    \
+                                 --dir <dir>         directory to store results in\n    \
+                                 --openings <file>   openings to use\n    \
+                                 --p1cmd <command>   command of the first program\n    \
+                                 --p1name <name>     name of the first program\n    \
+                                 --p2cmd <command>   command of the second program\n    \
+                                 --p2name <name>     name of the second program\n    \
+                                 --quiet             do not show the commands and answers\n    \
+                                 --rounds <n>        number of rounds (default 1)\n    \
+                                 --size <n>          board size (default 11)\n    \
+                                 --sprt <elo0>,<elo1>[,<alpha>,<beta>]\n    \
+                                 \x20                   stop once a sequential probability \
+                                 ratio\n    \
+                                 \x20                   test decides if p1 is elo0 or elo1 \
+                                 stronger\n    \
+                                 --type <type>       'iterative' (default) or 'random'\n\
+                                 %SRAND in a command is replaced by a random seed for each game.\n\
+                                 Results are for the colors the programs started with. They are \
+                                 flipped after swap-sides,\n\
+                                 but not after swap-pieces, which leaves each program its \
+                                 color.";
+
+impl TournamentConfig {
+    /// Parse the options which follow `coronene tournament`.
+    pub fn parse<I>(mut args: I) -> Result<TournamentConfig, String>
+        where I: Iterator<Item = String>
+    {
+        fn parse<T: ::std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("invalid value for {}: {}", name, value))
+        }
+        let mut config = TournamentConfig {
+            p1name: String::new(),
+            p1cmd: String::new(),
+            p2name: String::new(),
+            p2cmd: String::new(),
+            size: 11,
+            rounds: 1,
+            dir: String::new(),
+            openings: String::new(),
+            kind: Kind::Iterative,
+            verbose: true,
+            sprt: None,
+        };
+        while let Some(arg) = args.next() {
+            if arg == "--quiet" {
+                config.verbose = false;
+                continue;
+            }
+            let value = try!(args.next().ok_or(format!("missing value for {}", arg)));
+            match &arg[..] {
+                "--dir" => config.dir = value,
+                "--openings" => config.openings = value,
+                "--p1cmd" => config.p1cmd = value,
+                "--p1name" => config.p1name = value,
+                "--p2cmd" => config.p2cmd = value,
+                "--p2name" => config.p2name = value,
+                "--rounds" => config.rounds = try!(parse(&arg, &value)),
+                "--sprt" => config.sprt = Some(try!(Sprt::parse(&value))),
+                "--size" => {
+                    config.size = try!(parse(&arg, &value));
+                    if config.size <= 0 {
+                        return Err(format!("invalid value for {}: {}", arg, value));
+                    }
+                }
+                "--type" => {
+                    config.kind = match &value[..] {
+                        "iterative" => Kind::Iterative,
+                        "random" => Kind::Random,
+                        _ => return Err(format!("unknown tournament type: {}", value)),
+                    }
+                }
+                _ => return Err(format!("unknown option: {}", arg)),
+            }
+        }
+        if config.p1cmd.is_empty() || config.p2cmd.is_empty() || config.p1name.is_empty() ||
+           config.p2name.is_empty() || config.openings.is_empty() || config.dir.is_empty() {
+            return Err("the programs, their names, the openings and the directory are required"
+                           .into());
+        }
+        Ok(config)
+    }
+}
+
+/// An opening, with its weight in a random tournament.
+#[derive(Clone, Debug, PartialEq)]
+struct Opening {
+    weight: f64,
+    moves: String,
+}
+
+/// Parse an openings file: one opening on each line, as moves separated by spaces. In a random
+/// tournament each line starts with the opening's weight. Blank lines are skipped.
+fn parse_openings(text: &str, kind: Kind) -> Result<Vec<Opening>, String> {
+    let mut openings = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let opening = match kind {
+            Kind::Iterative => {
+                Opening {
+                    weight: 1.0,
+                    moves: line.into(),
+                }
+            }
+            Kind::Random => {
+                let mut parts = line.splitn(2, ' ');
+                let weight = parts.next().unwrap();
+                Opening {
+                    weight: try!(weight.parse()
+                                       .map_err(|_| format!("line {}: invalid weight", i + 1))),
+                    moves: parts.next().unwrap_or("").trim().into(),
+                }
+            }
+        };
+        openings.push(opening);
+    }
+    if openings.is_empty() {
+        return Err("no openings".into());
+    }
+    Ok(openings)
+}
+
+/// Which game of an iterative tournament is played where: its round, the index of its opening,
+/// and whether the first program plays Black.
+fn schedule(game: usize, num_openings: usize) -> (usize, usize, bool) {
+    let per_round = 2 * num_openings;
+    (game / per_round, game % per_round / 2, game % 2 == 0)
+}
+
+/// A finished game, as it is recorded in the results file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    pub index: usize,
+    pub round: usize,
+    pub opening: String,
+    pub black: String,
+    pub white: String,
+    /// The result according to each program, such as `B+`, or `?` if it couldn't say.
+    pub result_black: String,
+    pub result_white: String,
+    /// The number of moves, not counting a resignation.
+    pub length: usize,
+    /// Seconds used by each program.
+    pub time_black: f64,
+    pub time_white: f64,
+    /// What went wrong, if the game couldn't be finished.
+    pub error: Option<String>,
+}
+
+impl GameResult {
+    /// Format the game as a line of the results file.
+    pub fn to_line(&self) -> String {
+        format!("{:04}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.1}\t{:.1}\t{}\t{}",
+                self.index,
+                self.round,
+                self.opening,
+                self.black,
+                self.white,
+                self.result_black,
+                self.result_white,
+                self.length,
+                self.time_black,
+                self.time_white,
+                self.error.is_some() as u8,
+                self.error.as_ref().map_or("", |e| &e[..]))
+    }
+
+    /// Parse a line of the results file.
+    pub fn parse(line: &str) -> Result<GameResult, String> {
+        let fields: Vec<&str> = line.trim_end_matches(|c| c == '\n' || c == '\r')
+                                    .split('\t')
+                                    .collect();
+        let err = || format!("invalid results line: {}", line);
+        if fields.len() < 11 {
+            return Err(err());
+        }
+        Ok(GameResult {
+            index: try!(fields[0].parse().map_err(|_| err())),
+            round: try!(fields[1].parse().map_err(|_| err())),
+            opening: fields[2].into(),
+            black: fields[3].into(),
+            white: fields[4].into(),
+            result_black: fields[5].into(),
+            result_white: fields[6].into(),
+            length: try!(fields[7].parse().map_err(|_| err())),
+            time_black: try!(fields[8].parse().map_err(|_| err())),
+            time_white: try!(fields[9].parse().map_err(|_| err())),
+            error: if fields[10] == "0" {
+                None
+            } else {
+                Some(fields.get(11).map_or("", |e| *e).into())
+            },
+        })
+    }
+
+    /// The winner, if both programs agree on it.
+    pub fn winner(&self) -> Option<Color> {
+        let color = |result: &str| result.chars().next().and_then(|c| c.to_string().parse().ok());
+        match (color(&self.result_black), color(&self.result_white)) {
+            (Some(black), Some(white)) if black == white => Some(black),
+            _ => None,
+        }
+    }
+
+    /// The name of the program which won, if both programs agree on it.
+    pub fn winner_name(&self) -> Option<&str> {
+        self.winner().map(|color| {
+            match color {
+                Color::Black => &self.black[..],
+                Color::White => &self.white[..],
+            }
+        })
+    }
+}
+
+/// Parse the games of a results file, skipping comments.
+pub fn parse_results(text: &str) -> Result<Vec<GameResult>, String> {
+    text.lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(GameResult::parse)
+        .collect()
+}
+
+/// Read the games of a results file.
+pub fn load_results(path: &str) -> Result<Vec<GameResult>, String> {
+    let mut text = String::new();
+    try!(File::open(path)
+             .and_then(|mut f| f.read_to_string(&mut text))
+             .map_err(|e| format!("cannot read {}: {}", path, e)));
+    parse_results(&text)
+}
+
+fn timestamp() -> String {
+    time::strftime("%Y-%m-%d %H:%M:%S %Z", &time::now()).unwrap()
+}
+
+/// The `results` file of a tournament, which games are appended to as they finish.
+struct ResultsFile {
+    path: String,
+    /// The games already in the file.
+    games: Vec<GameResult>,
+}
+
+impl ResultsFile {
+    /// Open the results file, or create it with a header made from `info` if there isn't one.
+    fn open(path: &str, info: &[String]) -> Result<ResultsFile, String> {
+        let err = |e: ::std::io::Error| format!("cannot write {}: {}", path, e);
+        let games = if Path::new(path).exists() {
+            try!(load_results(path))
+        } else {
+            let mut header = String::from("# Game results file generated by coronene \
+                                           tournament.\n#\n");
+            for line in info {
+                header.push_str(&format!("# {}\n", line));
+            }
+            header.push_str("#\n# GAME\tROUND\tOPENING\tBLACK\tWHITE\tRES_B\tRES_W\tLENGTH\t\
+                             TIME_B\tTIME_W\tERR\tERR_MSG\n#\n");
+            try!(File::create(path).and_then(|mut f| f.write_all(header.as_bytes())).map_err(&err));
+            Vec::new()
+        };
+        let mut results = ResultsFile {
+            path: path.into(),
+            games: games,
+        };
+        try!(results.append(&format!("# Date: {}", timestamp())));
+        Ok(results)
+    }
+
+    fn add(&mut self, result: GameResult) -> Result<(), String> {
+        try!(self.append(&result.to_line()));
+        self.games.push(result);
+        Ok(())
+    }
+
+    fn append(&mut self, line: &str) -> Result<(), String> {
+        OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| writeln!(f, "{}", line))
+            .map_err(|e| format!("cannot write {}: {}", self.path, e))
+    }
+}
+
+/// Exchange Black and White in a result.
+fn flip_result(result: &str) -> String {
+    if result.starts_with('B') {
+        format!("W{}", &result[1..])
+    } else if result.starts_with('W') {
+        format!("B{}", &result[1..])
+    } else {
+        result.into()
+    }
+}
+
+/// Combine the results according to each program into one for the game record.
+fn merge_results(black: &str, white: &str) -> String {
+    if black == white {
+        black.into()
+    } else if black.starts_with("B+") && white.starts_with("B+") {
+        "B+".into()
+    } else if black.starts_with("W+") && white.starts_with("W+") {
+        "W+".into()
+    } else {
+        "?".into()
+    }
+}
+
+/// Play a tournament, or the rest of it if some of its games are already in the results file.
+pub fn run(config: &TournamentConfig) -> Result<(), String> {
+    let mut text = String::new();
+    try!(File::open(&config.openings)
+             .and_then(|mut f| f.read_to_string(&mut text))
+             .map_err(|e| format!("cannot read {}: {}", config.openings, e)));
+    let openings = try!(parse_openings(&text, config.kind));
+    try!(fs::create_dir_all(&config.dir)
+             .map_err(|e| format!("cannot create {}: {}", config.dir, e)));
+
+    let info = vec![format!("p1name: {}", config.p1name),
+                    format!("p1cmd: {}", config.p1cmd),
+                    format!("p2name: {}", config.p2name),
+                    format!("p2cmd: {}", config.p2cmd),
+                    format!("Boardsize: {}", config.size),
+                    format!("Rounds: {}", config.rounds),
+                    format!("Openings: {}", config.openings),
+                    format!("Directory: {}", config.dir),
+                    format!("Start Date: {}", timestamp())];
+    if config.verbose {
+        for line in &info {
+            println!("{}", line);
+        }
+    }
+    let mut results = try!(ResultsFile::open(&format!("{}/results", config.dir), &info));
+
+    let num_games = match config.kind {
+        Kind::Iterative => config.rounds * 2 * openings.len(),
+        Kind::Random => config.rounds,
+    };
+    let first = results.games.last().map_or(0, |game| game.index + 1);
+    let total_weight: f64 = openings.iter().map(|o| o.weight).sum();
+    for index in first..num_games {
+        if let Some(sprt) = config.sprt {
+            let (wins, losses) = analysis::score(&results.games, &config.p1name);
+            if sprt.test(wins, losses) != SprtResult::Continue {
+                let report = sprt.report(wins, losses);
+                println!("{}", report);
+                try!(results.append(&format!("# {}", report)));
+                break;
+            }
+        }
+        let (round, opening, p1_black) = match config.kind {
+            Kind::Iterative => {
+                let (round, opening, p1_black) = schedule(index, openings.len());
+                (round, &openings[opening], p1_black)
+            }
+            Kind::Random => {
+                let mut pick = rand::thread_rng().gen::<f64>() * total_weight;
+                let opening = openings.iter()
+                                      .find(|o| {
+                                          pick -= o.weight;
+                                          pick < 0.0
+                                      })
+                                      .unwrap_or(openings.last().unwrap());
+                (index, opening, index % 2 == 0)
+            }
+        };
+        let players = if p1_black {
+            [(&config.p1name, &config.p1cmd), (&config.p2name, &config.p2cmd)]
+        } else {
+            [(&config.p2name, &config.p2cmd), (&config.p1name, &config.p1cmd)]
+        };
+        let result = try!(play_game(config, index, round, &opening.moves, players));
+        if let Some(ref e) = result.error {
+            println!("Error: Game {}: {}", index, e);
+        }
+        try!(results.add(result));
+    }
+    Ok(())
+}
+
+/// The programs of a game in progress, and the game so far.
+struct GamePlayer {
+    /// Black's program and then White's, as they were when the game started.
+    programs: Vec<Program>,
+    /// Which program plays each color, which changes if they swap sides.
+    seats: [usize; 2],
+    board: Board,
+    moves: Vec<Move>,
+    /// Seconds used by each program.
+    elapsed: [f64; 2],
+    verbose: bool,
+}
+
+impl GamePlayer {
+    /// Send a command to a program, describing any failure by the color it started with.
+    fn send(&mut self, i: usize, cmd: &str) -> Result<String, String> {
+        self.programs[i].send(cmd).map_err(|e| {
+            let label = if i == 0 { "B" } else { "W" };
+            match e {
+                HtpError::Denied(reason) => format!("{}: {}", label, reason),
+                HtpError::Died => format!("{}: program died", label),
+            }
+        })
+    }
+
+    /// Play the opening, and then ask the programs for moves until the game is over.
+    fn play(&mut self, size: Coord, opening: &str) -> Result<(), String> {
+        for i in 0..2 {
+            try!(self.send(i, &format!("boardsize {} {}", size, size)));
+        }
+        let mut opening = opening.split_whitespace();
+        while self.board.winner().is_none() {
+            let color = self.board.to_play();
+            let seat = self.seats[color as usize];
+            let name = color.to_string().to_lowercase();
+            let (text, from_opening) = match opening.next() {
+                Some(m) => (m.to_lowercase(), true),
+                None => {
+                    let start = time::precise_time_s();
+                    let answer = try!(self.send(seat, &format!("genmove {}", name)));
+                    self.elapsed[seat] += time::precise_time_s() - start;
+                    (answer.to_lowercase(), false)
+                }
+            };
+            let m = try!(Move::parse(color, &text)
+                             .map_err(|_| format!("{}: invalid move: {}", color, text)));
+            if m != Move::Resign && !self.board.make_move(m) {
+                return Err(format!("{}: illegal move: {}", color, text));
+            }
+            self.moves.push(m);
+            // the opening is played for both programs, and other moves for the one which didn't
+            // generate them, so that both know when the game ends by resignation
+            for i in 0..2 {
+                if from_opening || i != seat {
+                    try!(self.send(i, &format!("play {} {}", name, text)));
+                }
+            }
+            if m == Move::Resign {
+                break;
+            }
+            if let Move::Swap { kind: SwapKind::Sides, .. } = m {
+                self.seats.swap(0, 1);
+            }
+            if self.verbose {
+                try!(self.send(0, "showboard"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The winner according to each program. After a swap of sides, these are turned back into
+    /// the colors the programs started with. A swap of pieces leaves the programs their colors, so
+    /// its results are kept as they are.
+    fn results(&mut self) -> Vec<String> {
+        let swapped = self.seats != [0, 1];
+        (0..2)
+            .map(|i| {
+                let result = self.send(i, "final_score").unwrap_or("?".into());
+                if swapped { flip_result(&result) } else { result }
+            })
+            .collect()
+    }
+}
+
+/// Play one game, with the (name, command) of Black and of White, and save its record.
+fn play_game(config: &TournamentConfig,
+             index: usize,
+             round: usize,
+             opening: &str,
+             players: [(&String, &String); 2])
+             -> Result<GameResult, String> {
+    if config.verbose {
+        println!("\n===========================================================\n\
+                  Game {}\n\
+                  ===========================================================",
+                 index);
+    }
+    let mut programs = Vec::new();
+    for (i, &(name, cmd)) in players.iter().enumerate() {
+        let cmd = cmd.replace("%SRAND", &rand::thread_rng().gen_range(0, 1000000).to_string());
+        let log = format!("{}/{}-{}", config.dir, name, index);
+        programs.push(try!(Program::new(if i == 0 { "B" } else { "W" },
+                                        &cmd,
+                                        &format!("{}.log", log),
+                                        &format!("{}-stderr.log", log),
+                                        config.verbose)));
+    }
+    let names: Vec<String> = programs.iter_mut().map(|p| p.name()).collect();
+    let mut game_player = GamePlayer {
+        programs: programs,
+        seats: [0, 1],
+        board: Board::new((config.size, config.size)),
+        moves: Vec::new(),
+        elapsed: [0.0; 2],
+        verbose: config.verbose,
+    };
+    let error = game_player.play(config.size, opening).err();
+    let results = if error.is_none() {
+        game_player.results()
+    } else {
+        vec!["?".into(), "?".into()]
+    };
+    let result = GameResult {
+        index: index,
+        round: round,
+        opening: opening.into(),
+        black: players[0].0.clone(),
+        white: players[1].0.clone(),
+        result_black: results[0].clone(),
+        result_white: results[1].clone(),
+        length: game_player.moves.iter().filter(|&&m| m != Move::Resign).count(),
+        time_black: game_player.elapsed[0],
+        time_white: game_player.elapsed[1],
+        error: error,
+    };
+
+    let mut game = Game::new(game_player.board.dimensions(), game_player.moves.clone());
+    let comment = format!("Generated by coronene tournament.\n\
+                           Black Cmd: {}\n\
+                           White Cmd: {}\n\
+                           Time: {}\n\
+                           Result according to B: {}\n\
+                           Result according to W: {}",
+                          game_player.programs[0].command(),
+                          game_player.programs[1].command(),
+                          timestamp(),
+                          result.result_black,
+                          result.result_white);
+    let info = vec![("PB", names[0].clone()),
+                    ("PW", names[1].clone()),
+                    ("RE", merge_results(&result.result_black, &result.result_white)),
+                    ("DT", time::strftime("%Y-%m-%d", &time::now()).unwrap()),
+                    ("GN", format!("{}/{:04}", config.dir, index)),
+                    ("US", "coronene tournament".into()),
+                    ("GC", comment)];
+    for (ident, value) in info {
+        game.info.push(Property {
+            ident: ident.into(),
+            values: vec![value],
+        });
+    }
+    try!(game.save(&format!("{}/{:04}.sgf", config.dir, index)));
+    for program in game_player.programs {
+        program.quit();
+    }
+    Ok(result)
+}
+
+#[test]
+fn test_tournament() {
+    // each round plays every opening with both colors
+    let openings = parse_openings("a1\n\nb2 c3\n", Kind::Iterative).unwrap();
+    assert_eq!(openings.len(), 2);
+    assert_eq!(openings[1].moves, "b2 c3");
+    let games: Vec<_> = (0..6).map(|i| schedule(i, openings.len())).collect();
+    assert_eq!(games,
+               vec![(0, 0, true), (0, 0, false), (0, 1, true), (0, 1, false), (1, 0, true),
+                    (1, 0, false)]);
+    let weighted = parse_openings("2.5 a1 b2\n1 c3", Kind::Random).unwrap();
+    assert_eq!(weighted[0],
+               Opening {
+                   weight: 2.5,
+                   moves: "a1 b2".into(),
+               });
+    assert!(parse_openings("x a1", Kind::Random).is_err());
+    assert!(parse_openings("\n", Kind::Iterative).is_err());
+
+    // a results file is resumed after its last game
+    let result = GameResult {
+        index: 7,
+        round: 1,
+        opening: "a1".into(),
+        black: "new".into(),
+        white: "old".into(),
+        result_black: "B+".into(),
+        result_white: "B+".into(),
+        length: 31,
+        time_black: 12.3,
+        time_white: 5.0,
+        error: None,
+    };
+    assert_eq!(result.to_line(), "0007\t1\ta1\tnew\told\tB+\tB+\t31\t12.3\t5.0\t0\t");
+    let text = format!("# header\n#\n{}\n# Date: today\n", result.to_line());
+    assert_eq!(parse_results(&text), Ok(vec![result.clone()]));
+    assert_eq!(parse_results("# only a header\n"), Ok(vec![]));
+    assert_eq!(result.winner_name(), Some("new"));
+
+    // after swap-sides the program which started as White plays Black, but after swap-pieces it
+    // keeps White, and either way the result is for the colors the programs started with
+    let dir = ::std::env::temp_dir().join(format!("coronene-test-{}", ::std::process::id()));
+    let mut config = TournamentConfig::parse(vec!["--p1name", "p1", "--p1cmd", "-", "--p2name",
+                                                  "p2", "--p2cmd", "-", "--openings", "-",
+                                                  "--dir", dir.to_str().unwrap(), "--size", "3",
+                                                  "--quiet"]
+                                                 .into_iter()
+                                                 .map(String::from))
+                         .unwrap();
+    fs::create_dir_all(&config.dir).unwrap();
+    // a program which resigns at once, and only knows that `winner` has won once someone has
+    // resigned
+    let resigner = |winner: &str| {
+        format!("r=?; while read cmd c m; do case $cmd in genmove) r={0}; echo '= resign';; \
+                 play) [ \"$m\" = resign ] && r={0}; echo '=';; final_score) echo \"= $r+\";; \
+                 *) echo '=';; esac; echo; done",
+                winner)
+    };
+    for &(opening, winner) in &[("a1 swap-sides", "B"), ("a1 swap-pieces", "W")] {
+        config.p1cmd = resigner(winner);
+        config.p2cmd = config.p1cmd.clone();
+        let players = [(&config.p1name, &config.p1cmd), (&config.p2name, &config.p2cmd)];
+        let result = play_game(&config, 0, 0, opening, players).unwrap();
+        assert_eq!(result.error, None);
+        assert_eq!(result.to_line(),
+                   format!("0000\t0\t{}\tp1\tp2\tW+\tW+\t2\t0.0\t0.0\t0\t", opening));
+        assert_eq!(result.winner_name(), Some("p2"));
+    }
+    fs::remove_dir_all(&config.dir).unwrap();
+
+    assert_eq!(flip_result("B+"), "W+");
+    assert_eq!(flip_result("?"), "?");
+    assert_eq!(merge_results("B+", "B+resign"), "B+");
+    assert_eq!(merge_results("B+", "W+"), "?");
+}
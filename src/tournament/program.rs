@@ -0,0 +1,128 @@
+//! An HTP engine running in a child process.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Why a command failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HtpError {
+    /// The program answered with an error message.
+    Denied(String),
+    /// The program exited or closed its output.
+    Died,
+}
+
+pub struct Program {
+    /// The color the program was created to play, which prefixes its lines in the log.
+    label: String,
+    command: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    /// A transcript of every command and answer.
+    log: File,
+    verbose: bool,
+}
+
+impl Program {
+    /// Start a program with a shell command. Its standard error is written to `stderr_log`, and
+    /// the commands sent to it and its answers to `log`.
+    pub fn new(label: &str,
+               command: &str,
+               log: &str,
+               stderr_log: &str,
+               verbose: bool)
+               -> Result<Program, String> {
+        if verbose {
+            println!("Creating program: {}", command);
+        }
+        let mut log = try!(File::create(log).map_err(|e| format!("cannot write {}: {}", log, e)));
+        let stderr = try!(File::create(stderr_log)
+                              .map_err(|e| format!("cannot write {}: {}", stderr_log, e)));
+        try!(writeln!(log, "# {}", command).map_err(|e| e.to_string()));
+        let mut child = try!(Command::new("sh")
+                                 .arg("-c")
+                                 .arg(command)
+                                 .stdin(Stdio::piped())
+                                 .stdout(Stdio::piped())
+                                 .stderr(stderr)
+                                 .spawn()
+                                 .map_err(|e| format!("cannot run {}: {}", command, e)));
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Ok(Program {
+            label: label.into(),
+            command: command.into(),
+            child: child,
+            stdin: stdin,
+            stdout: stdout,
+            log: log,
+            verbose: verbose,
+        })
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Send a command and wait for the answer, without the leading `= `.
+    pub fn send(&mut self, cmd: &str) -> Result<String, HtpError> {
+        let _ = writeln!(self.log, ">{}", cmd);
+        if self.verbose {
+            println!("{}< {}", self.label, cmd);
+        }
+        if writeln!(self.stdin, "{}", cmd).and_then(|_| self.stdin.flush()).is_err() {
+            return Err(HtpError::Died);
+        }
+
+        // the answer ends with an empty line
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            match self.stdout.read_line(&mut line) {
+                Ok(0) | Err(_) => return Err(HtpError::Died),
+                Ok(_) => (),
+            }
+            let _ = write!(self.log, "<{}", line);
+            if self.verbose {
+                print!("{}> {}", self.label, line);
+            }
+            let line = line.trim_end().to_string();
+            if line.is_empty() && !lines.is_empty() {
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        let answer = lines.join("\n");
+        if answer.starts_with('=') {
+            Ok(answer[1..].trim().into())
+        } else {
+            Err(HtpError::Denied(answer.trim_start_matches('?').trim().into()))
+        }
+    }
+
+    /// The program's name and version.
+    pub fn name(&mut self) -> String {
+        match (self.send("name"), self.send("version")) {
+            (Ok(name), Ok(version)) => format!("{} {}", name, version),
+            (Ok(name), Err(_)) => name,
+            _ => "?".into(),
+        }
+    }
+
+    /// Ask the program to quit, and wait for it to exit.
+    pub fn quit(mut self) {
+        let _ = self.send("quit");
+    }
+}
+
+impl Drop for Program {
+    // a program which doesn't answer `quit` is stopped anyway
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
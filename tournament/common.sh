@@ -24,27 +24,27 @@ while true ; do
     esac
 done
 
+# The tournament runner is built into coronene
+TOURNAMENT="../target/release/coronene tournament"
+
 # Set default openings if user does not specify them
 if [ "$OPENINGS" == "" ]; then
     OPENINGS="openings/"$SIZE"x"$SIZE"-all-1ply"
 fi
 
-# Runs the tournament. 
+# Runs the tournament between two programs. Each is given a random seed
+# for every game, so that the games can be replayed.
 run_tournament() 
 {
-    if [ $# != 4 ]; then
+    if [ $# != 2 ]; then
         usage;
         exit 1;
     fi
 
     PROGRAM1=$1
-    CONFIG1=$2
-    PROGNAME1=`basename $PROGRAM1`
-    NAME1=$PROGNAME1-$CONFIG1
-    PROGRAM2=$3
-    CONFIG2=$4
-    PROGNAME2=`basename $PROGRAM2`
-    NAME2=$PROGNAME2-$CONFIG2
+    NAME1=`basename $PROGRAM1`
+    PROGRAM2=$2
+    NAME2=`basename $PROGRAM2`
 
     # Distinguish between the instances if doing self-play so that the 
     # logfiles are not clobbered.
@@ -57,11 +57,11 @@ run_tournament()
 
     mkdir -p $DIRECTORY
 
-    ./twogtp.py \
+    $TOURNAMENT \
         --type $TYPE \
         --dir "$DIRECTORY" \
         --openings $OPENINGS \
         --size $SIZE --rounds $ROUNDS \
-        --p1cmd "$PROGRAM1 --config $CONFIG1.htp" --p1name $NAME1 \
-        --p2cmd "$PROGRAM2 --config $CONFIG2.htp" --p2name $NAME2
+        --p1cmd "$PROGRAM1 --seed %SRAND" --p1name $NAME1 \
+        --p2cmd "$PROGRAM2 --seed %SRAND" --p2name $NAME2
 }
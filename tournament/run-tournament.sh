@@ -26,11 +26,11 @@ B=$2
 DIRECTORY="jobs/$A-vs-$B"
 mkdir -p $DIRECTORY
 
-./twogtp.py \
+$TOURNAMENT \
 --type $TYPE \
 --dir $DIRECTORY \
 --openings $OPENINGS \
 --size $SIZE --rounds $ROUNDS \
---p1cmd "builds/$A" --p1name "$A" \
---p2cmd "builds/$B" --p2name "$B"
+--p1cmd "builds/$A --seed %SRAND" --p1name "$A" \
+--p2cmd "builds/$B --seed %SRAND" --p2name "$B"
 